@@ -10,6 +10,7 @@ use crate::{
 		find_location, LocationError,
 	},
 	object::{
+		file_identifier::CustomKindDefinition,
 		fs::{
 			copy::FileCopierJobInit, cut::FileCutterJobInit, delete::FileDeleterJobInit,
 			erase::FileEraserJobInit,
@@ -74,7 +75,7 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 						.await?
 						.and_then(|obj| {
 							Some(match obj.kind {
-								Some(v) if v == ObjectKind::Image as i32 => {
+								Some(v) if v == ObjectKind::Image.as_i32() => {
 									MediaMetadata::Image(Box::new(
 										media_data_image_from_prisma_data(obj.media_data?).ok()?,
 									))
@@ -87,6 +88,23 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 						})
 				})
 		})
+		.procedure("resolveCustomKindName", {
+			#[derive(Type, Deserialize)]
+			pub struct ResolveCustomKindNameArgs {
+				pub definitions: Vec<CustomKindDefinition>,
+				pub id: u16,
+			}
+			// Custom kind definitions are job-scoped and never persisted, so the
+			// frontend is the source of truth for them; it passes back whichever
+			// set it used to start the identifier job alongside the id it wants
+			// resolved, rather than this being backed by a database table.
+			R.query(|_, args: ResolveCustomKindNameArgs| async move {
+				Ok(
+					CustomKindDefinition::resolve_name(&args.definitions, args.id)
+						.map(str::to_string),
+				)
+			})
+		})
 		.procedure("getEphemeralMediaData", {
 			R.query(|_, full_path: PathBuf| async move {
 				let Some(extension) = full_path.extension().and_then(|ext| ext.to_str()) else {
@@ -4,8 +4,9 @@ use crate::{
 	library::Library,
 	location::{find_location, LocationError},
 	object::{
-		file_identifier::file_identifier_job::FileIdentifierJobInit, media::MediaProcessorJobInit,
-		validation::validator_job::ObjectValidatorJobInit,
+		file_identifier::file_identifier_job::FileIdentifierJobInit,
+		media::MediaProcessorJobInit,
+		validation::{cas_verifier_job::CasVerifierJobInit, validator_job::ObjectValidatorJobInit},
 	},
 	prisma::{job, location, SortOrder},
 };
@@ -300,26 +301,48 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 					.map_err(Into::into)
 				})
 		})
-		.procedure("identifyUniqueFiles", {
+		.procedure("casVerifier", {
 			#[derive(Type, Deserialize)]
-			pub struct IdentifyUniqueFilesArgs {
+			pub struct CasVerifierArgs {
 				pub id: location::id::Type,
 				pub path: PathBuf,
+				#[serde(default)]
+				pub repair: bool,
 			}
 
-			R.with2(library()).mutation(
-				|(node, library), args: IdentifyUniqueFilesArgs| async move {
+			R.with2(library())
+				.mutation(|(node, library), args: CasVerifierArgs| async move {
 					let Some(location) = find_location(&library, args.id).exec().await? else {
 						return Err(LocationError::IdNotFound(args.id).into());
 					};
 
-					Job::new(FileIdentifierJobInit {
+					Job::new(CasVerifierJobInit {
 						location,
 						sub_path: Some(args.path),
+						repair: args.repair,
 					})
 					.spawn(&node, &library)
 					.await
 					.map_err(Into::into)
+				})
+		})
+		.procedure("identifyUniqueFiles", {
+			#[derive(Type, Deserialize)]
+			pub struct IdentifyUniqueFilesArgs {
+				pub id: location::id::Type,
+				pub path: PathBuf,
+			}
+
+			R.with2(library()).mutation(
+				|(node, library), args: IdentifyUniqueFilesArgs| async move {
+					let Some(location) = find_location(&library, args.id).exec().await? else {
+						return Err(LocationError::IdNotFound(args.id).into());
+					};
+
+					Job::new(FileIdentifierJobInit::new(location, Some(args.path)))
+						.spawn(&node, &library)
+						.await
+						.map_err(Into::into)
 				},
 			)
 		})
@@ -30,6 +30,16 @@ struct SearchData<T> {
 	items: Vec<T>,
 }
 
+/// Args for the `search.pathsInLocation` query, also reused as the arg type
+/// for the location-scoped [`invalidate_query!`] fired from the file
+/// identifier job's `finalize`, so a run that only touched one location
+/// doesn't force every explorer view watching `search.paths` to refetch.
+#[derive(Serialize, Deserialize, Type, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LocationIdArgs {
+	pub location_id: location::id::Type,
+}
+
 #[derive(Deserialize, Default, Type, Debug)]
 #[serde(rename_all = "camelCase")]
 struct OptionalRange<T> {
@@ -560,6 +570,45 @@ pub fn mount() -> AlphaRouter<Ctx> {
 				},
 			)
 		})
+		.procedure("pathsInLocation", {
+			R.with2(library()).query(
+				|(node, library), LocationIdArgs { location_id }| async move {
+					let Library { db, .. } = library.as_ref();
+
+					let file_paths = db
+						.file_path()
+						.find_many(vec![file_path::location_id::equals(Some(location_id))])
+						.take(MAX_TAKE as i64)
+						.include(file_path_with_object::include())
+						.exec()
+						.await?;
+
+					let mut items = Vec::with_capacity(file_paths.len());
+
+					for file_path in file_paths {
+						let thumbnail_exists_locally = if let Some(cas_id) = &file_path.cas_id {
+							library
+								.thumbnail_exists(&node, cas_id)
+								.await
+								.map_err(LocationError::from)?
+						} else {
+							false
+						};
+
+						items.push(ExplorerItem::Path {
+							has_local_thumbnail: thumbnail_exists_locally,
+							thumbnail_key: file_path.cas_id.as_ref().map(|i| get_thumb_key(i)),
+							item: file_path,
+						})
+					}
+
+					Ok(SearchData {
+						items,
+						cursor: None,
+					})
+				},
+			)
+		})
 		.procedure("pathsCount", {
 			#[derive(Deserialize, Type, Debug)]
 			#[serde(rename_all = "camelCase")]
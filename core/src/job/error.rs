@@ -7,6 +7,8 @@ use crate::{
 	util::{db::MissingFieldError, error::FileIOError},
 };
 
+use std::path::PathBuf;
+
 use prisma_client_rust::QueryError;
 use rmp_serde::{decode::Error as DecodeError, encode::Error as EncodeError};
 use sd_crypto::Error as CryptoError;
@@ -49,6 +51,8 @@ pub enum JobError {
 	MissingFromDb(&'static str, String),
 	#[error("Thumbnail skipped")]
 	ThumbnailSkipped,
+	#[error("location root is not accessible: {}", .0.display())]
+	LocationUnavailable(PathBuf),
 
 	// Specific job errors
 	#[error(transparent)]
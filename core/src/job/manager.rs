@@ -9,7 +9,7 @@ use crate::{
 			erase::FileEraserJobInit,
 		},
 		media::media_processor::MediaProcessorJobInit,
-		validation::validator_job::ObjectValidatorJobInit,
+		validation::{cas_verifier_job::CasVerifierJobInit, validator_job::ObjectValidatorJobInit},
 	},
 	prisma::job,
 	Node,
@@ -392,6 +392,7 @@ fn initialize_resumable_job(
 			IndexerJobInit,
 			FileIdentifierJobInit,
 			ObjectValidatorJobInit,
+			CasVerifierJobInit,
 			FileCutterJobInit,
 			FileCopierJobInit,
 			FileDeleterJobInit,
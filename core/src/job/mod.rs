@@ -287,6 +287,20 @@ where
 	pub run_metadata: Job::RunMetadata,
 }
 
+/// Borrowing mirror of [`JobState`], for checkpointing the state of a job
+/// that's still running (and so still owns its fields) without having to move
+/// them out first. Serialize-only: there's no matching `Deserialize` impl, as
+/// a checkpoint is only ever read back through [`JobState`] after a job has
+/// stopped and its `data` column has been reloaded via [`Job::new_from_report`].
+#[derive(Serialize)]
+struct JobStateRef<'a, Job: StatefulJob> {
+	init: &'a Job,
+	data: Option<&'a Job::Data>,
+	steps: &'a VecDeque<Job::Step>,
+	step_number: usize,
+	run_metadata: &'a Job::RunMetadata,
+}
+
 pub struct JobInitOutput<RunMetadata, Step> {
 	run_metadata: RunMetadata,
 	steps: VecDeque<Step>,
@@ -842,6 +856,15 @@ impl<SJob: StatefulJob> DynJob for Job<SJob> {
 								}
 								Err(e) if matches!(e, JobError::EarlyFinish { .. }) => {
 									info!("{e}");
+									// Unlike a normal step, an early finish doesn't get to
+									// report `maybe_more_steps`/checkpoint above, so nothing
+									// else stops the outer `while job_should_run &&
+									// !steps.is_empty()` loop on its own: without clearing
+									// `job_should_run` and the remaining queue here, every
+									// already-queued step would still get popped and spawned,
+									// each immediately hitting this same early finish again.
+									job_should_run = false;
+									steps.clear();
 									break;
 								}
 								Err(e) => return Err(e),
@@ -849,6 +872,22 @@ impl<SJob: StatefulJob> DynJob for Job<SJob> {
 							// remove the step from the queue
 							step_number += 1;
 
+							// Checkpoint the state reached by this step so a crash or
+							// unclean shutdown resumes from here, rather than falling
+							// back to a fresh `init` that would re-scan everything
+							// already processed. `rmp_serde::to_vec_named` failures are
+							// swallowed rather than aborting the job: losing a single
+							// checkpoint just means resuming from the previous one.
+							if let Ok(state) = rmp_serde::to_vec_named(&JobStateRef {
+								init: stateful_job.as_ref(),
+								data: Some(working_data_arc.as_ref()),
+								steps: &steps,
+								step_number,
+								run_metadata: &run_metadata,
+							}) {
+								ctx.checkpoint(state);
+							}
+
 							break;
 						}
 					}
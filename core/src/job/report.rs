@@ -19,6 +19,10 @@ pub enum JobReportUpdate {
 	TaskCount(usize),
 	CompletedTaskCount(usize),
 	Message(String),
+	/// Bytes actually read and hashed so far, versus the total expected for the
+	/// whole job, so a step dominated by a handful of huge files still shows
+	/// meaningful movement between `CompletedTaskCount` ticks.
+	BytesProgress { bytes_done: u64, bytes_total: u64 },
 }
 
 job::select!(job_without_data {
@@ -59,6 +63,9 @@ pub struct JobReport {
 
 	pub message: String,
 	pub estimated_completion: DateTime<Utc>,
+
+	pub bytes_done: u64,
+	pub bytes_total: u64,
 }
 
 impl Display for JobReport {
@@ -106,6 +113,8 @@ impl TryFrom<job::Data> for JobReport {
 			estimated_completion: data
 				.date_estimated_completion
 				.map_or(Utc::now(), DateTime::into),
+			bytes_done: 0,
+			bytes_total: 0,
 		})
 	}
 }
@@ -148,6 +157,8 @@ impl TryFrom<job_without_data::Data> for JobReport {
 			estimated_completion: data
 				.date_estimated_completion
 				.map_or(Utc::now(), DateTime::into),
+			bytes_done: 0,
+			bytes_total: 0,
 		})
 	}
 }
@@ -171,6 +182,8 @@ impl JobReport {
 			completed_task_count: 0,
 			message: String::new(),
 			estimated_completion: Utc::now(),
+			bytes_done: 0,
+			bytes_total: 0,
 		}
 	}
 
@@ -321,6 +334,8 @@ impl JobReportBuilder {
 			completed_task_count: 0,
 			message: String::new(),
 			estimated_completion: Utc::now(),
+			bytes_done: 0,
+			bytes_total: 0,
 		}
 	}
 
@@ -15,7 +15,7 @@ use serde_json::json;
 use specta::Type;
 use tokio::{
 	select,
-	sync::{mpsc, oneshot, watch},
+	sync::{mpsc, oneshot, watch, Notify},
 	time::Instant,
 };
 use tracing::{debug, error, info, trace, warn};
@@ -32,12 +32,15 @@ pub struct JobProgressEvent {
 	pub completed_task_count: i32,
 	pub message: String,
 	pub estimated_completion: DateTime<Utc>,
+	pub bytes_done: u64,
+	pub bytes_total: u64,
 }
 
 // used to update the worker state from inside the worker thread
 #[derive(Debug)]
 pub enum WorkerEvent {
 	Progressed(Vec<JobReportUpdate>),
+	Checkpoint(Vec<u8>),
 	Stop,
 }
 
@@ -50,10 +53,52 @@ pub enum WorkerCommand {
 	Shutdown(Instant, oneshot::Sender<()>),
 }
 
+/// Shared pause state between a [`Worker`] and the [`WorkerContext`] it hands
+/// to the job it's running, so a step can block itself between self-contained
+/// units of work (e.g. chunks) without the job runner having to abort
+/// anything to honor a pause. Kept as its own small type, rather than inlined
+/// as fields on [`WorkerContext`], so it can be unit tested without needing a
+/// full `Library`/`Node`.
+#[derive(Debug, Clone)]
+pub(super) struct PauseState {
+	paused: Arc<AtomicBool>,
+	resume_notify: Arc<Notify>,
+}
+
+impl PauseState {
+	fn new() -> Self {
+		Self {
+			paused: Arc::new(AtomicBool::new(false)),
+			resume_notify: Arc::new(Notify::new()),
+		}
+	}
+
+	fn pause(&self) {
+		self.paused.store(true, Ordering::Relaxed);
+	}
+
+	fn resume(&self) {
+		self.paused.store(false, Ordering::Relaxed);
+		self.resume_notify.notify_waiters();
+	}
+
+	fn is_paused(&self) -> bool {
+		self.paused.load(Ordering::Relaxed)
+	}
+
+	async fn wait_if_paused(&self) {
+		while self.is_paused() {
+			self.resume_notify.notified().await;
+		}
+	}
+}
+
 pub struct WorkerContext {
 	pub library: Arc<Library>,
 	pub node: Arc<Node>,
 	pub(super) events_tx: mpsc::UnboundedSender<WorkerEvent>,
+	pub(super) canceled: Arc<AtomicBool>,
+	pub(super) pause_state: PauseState,
 }
 
 impl fmt::Debug for WorkerContext {
@@ -85,6 +130,44 @@ impl WorkerContext {
 			})
 			.ok();
 	}
+
+	/// Persists a serialized snapshot of the job's state as it stands right
+	/// now, so a crash or unclean shutdown can resume from here instead of
+	/// falling back to `init` from scratch. Unlike [`Self::progress`], this
+	/// is written straight to the `job`'s `data` column rather than just the
+	/// in-memory report, at the cost of a DB round trip, so callers should
+	/// checkpoint once per step rather than more granularly than that.
+	pub fn checkpoint(&self, state: Vec<u8>) {
+		self.events_tx
+			.send(WorkerEvent::Checkpoint(state))
+			.map_err(|err| {
+				tracing::error!("Error sending worker context checkpoint event: {}", err);
+			})
+			.ok();
+	}
+
+	/// Lets a long-running step poll for cancellation without waiting for the
+	/// job runner's step-level `select!` to abort it, so it can bail out
+	/// between sub-phases (e.g. before issuing writes) instead of mid-write.
+	pub fn is_canceled(&self) -> bool {
+		self.canceled.load(Ordering::Relaxed)
+	}
+
+	/// Mirrors [`Self::is_canceled`], but for pausing: lets a step check
+	/// whether the worker has been paused without waiting for the job
+	/// runner's step-level `select!` to notice.
+	pub fn is_paused(&self) -> bool {
+		self.pause_state.is_paused()
+	}
+
+	/// Blocks the caller while the worker is paused, without tearing down
+	/// anything the caller is holding on to (e.g. a chunk cursor). Intended
+	/// to be awaited between self-contained units of work, such as between
+	/// chunks in a batched step, so a pause takes effect without losing
+	/// progress made on the current step.
+	pub async fn wait_if_paused(&self) {
+		self.pause_state.wait_if_paused().await;
+	}
 }
 
 // a worker is a dedicated thread that runs a single job
@@ -93,7 +176,8 @@ pub struct Worker {
 	commands_tx: mpsc::Sender<WorkerCommand>,
 	report_watch_tx: Arc<watch::Sender<JobReport>>,
 	report_watch_rx: watch::Receiver<JobReport>,
-	paused: AtomicBool,
+	pause_state: PauseState,
+	canceled: Arc<AtomicBool>,
 }
 
 impl Worker {
@@ -131,6 +215,9 @@ impl Worker {
 		let (report_watch_tx, report_watch_rx) = watch::channel(report.clone());
 		let report_watch_tx = Arc::new(report_watch_tx);
 
+		let canceled = Arc::new(AtomicBool::new(false));
+		let pause_state = PauseState::new();
+
 		// spawn task to handle running the job
 		tokio::spawn(Self::do_work(
 			id,
@@ -145,19 +232,25 @@ impl Worker {
 			commands_rx,
 			library,
 			node,
+			Arc::clone(&canceled),
+			pause_state.clone(),
 		));
 
 		Ok(Self {
 			commands_tx,
 			report_watch_tx,
 			report_watch_rx,
-			paused: AtomicBool::new(false),
+			pause_state,
+			canceled,
 		})
 	}
 
 	pub async fn pause(&self) {
 		if self.report_watch_rx.borrow().status == JobStatus::Running {
-			self.paused.store(true, Ordering::Relaxed);
+			// Set eagerly so a step already in flight can notice via
+			// `WorkerContext::is_paused` and block itself between chunks,
+			// instead of only pausing once this command is dequeued.
+			self.pause_state.pause();
 			if self
 				.commands_tx
 				.send(WorkerCommand::Pause(Instant::now()))
@@ -172,7 +265,7 @@ impl Worker {
 
 	pub async fn resume(&self) {
 		if self.report_watch_rx.borrow().status == JobStatus::Paused {
-			self.paused.store(false, Ordering::Relaxed);
+			self.pause_state.resume();
 			if self
 				.commands_tx
 				.send(WorkerCommand::Resume(Instant::now()))
@@ -187,6 +280,11 @@ impl Worker {
 
 	pub async fn cancel(&self) {
 		if self.report_watch_rx.borrow().status != JobStatus::Canceled {
+			// Set eagerly so a step already in flight can notice via
+			// `WorkerContext::is_canceled` and bail out on its own, instead of
+			// only being torn down once this command is dequeued.
+			self.canceled.store(true, Ordering::Relaxed);
+
 			let (tx, rx) = oneshot::channel();
 			if self
 				.commands_tx
@@ -218,7 +316,7 @@ impl Worker {
 	}
 
 	pub fn is_paused(&self) -> bool {
-		self.paused.load(Ordering::Relaxed)
+		self.pause_state.is_paused()
 	}
 
 	fn track_progress(
@@ -247,6 +345,14 @@ impl Worker {
 					trace!("job {} message: {}", report.id, message);
 					report.message = message;
 				}
+
+				JobReportUpdate::BytesProgress {
+					bytes_done,
+					bytes_total,
+				} => {
+					report.bytes_done = bytes_done;
+					report.bytes_total = bytes_total;
+				}
 			}
 		}
 
@@ -272,6 +378,8 @@ impl Worker {
 				old.completed_task_count = report.completed_task_count;
 				old.estimated_completion = report.estimated_completion;
 				old.message = report.message.clone();
+				old.bytes_done = report.bytes_done;
+				old.bytes_total = report.bytes_total;
 			});
 			*last_report_watch_update = Instant::now();
 		}
@@ -283,9 +391,27 @@ impl Worker {
 			completed_task_count: report.completed_task_count,
 			estimated_completion: report.estimated_completion,
 			message: report.message.clone(),
+			bytes_done: report.bytes_done,
+			bytes_total: report.bytes_total,
 		}));
 	}
 
+	/// Writes a checkpoint sent via [`WorkerContext::checkpoint`] straight to
+	/// the job's `data` column, independent of the in-memory report watched
+	/// by `track_progress`, so it survives even if the process is killed
+	/// before a graceful pause/shutdown gets a chance to persist anything.
+	async fn checkpoint(report: &mut JobReport, state: Vec<u8>, library: &Library) {
+		if report.status != JobStatus::Running {
+			return;
+		}
+
+		report.data = Some(state);
+
+		if let Err(e) = report.update(library).await {
+			error!("failed to persist job checkpoint: {:#?}", e);
+		}
+	}
+
 	async fn do_work(
 		worker_id: Uuid,
 		JobWorkTable {
@@ -299,6 +425,8 @@ impl Worker {
 		commands_rx: mpsc::Receiver<WorkerCommand>,
 		library: Arc<Library>,
 		node: Arc<Node>,
+		canceled: Arc<AtomicBool>,
+		pause_state: PauseState,
 	) {
 		let (events_tx, mut events_rx) = mpsc::unbounded_channel();
 
@@ -307,6 +435,8 @@ impl Worker {
 				library: library.clone(),
 				node: node.clone(),
 				events_tx,
+				canceled,
+				pause_state,
 			},
 			commands_rx,
 		);
@@ -332,6 +462,9 @@ impl Worker {
 										&library
 									);
 								}
+								WorkerEvent::Checkpoint(state) => {
+									Self::checkpoint(&mut report, state, &library).await;
+								}
 								WorkerEvent::Stop => {
 									break 'job job_result;
 								},
@@ -353,6 +486,9 @@ impl Worker {
 								&library
 							)
 						}
+						WorkerEvent::Checkpoint(state) => {
+							Self::checkpoint(&mut report, state, &library).await;
+						}
 						WorkerEvent::Stop => {events_ended = true;},
 					}
 				}
@@ -530,3 +666,37 @@ fn invalidate_queries(library: &Library) {
 	invalidate_query!(library, "jobs.isActive");
 	invalidate_query!(library, "jobs.reports");
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::sync::atomic::AtomicUsize;
+
+	use tokio::time::sleep;
+
+	#[tokio::test]
+	async fn paused_state_blocks_processing_until_resumed() {
+		let pause_state = PauseState::new();
+		pause_state.pause();
+
+		let processed = Arc::new(AtomicUsize::new(0));
+		let loop_processed = Arc::clone(&processed);
+		let loop_pause_state = pause_state.clone();
+
+		let handle = tokio::spawn(async move {
+			for _ in 0..3 {
+				loop_pause_state.wait_if_paused().await;
+				loop_processed.fetch_add(1, Ordering::Relaxed);
+			}
+		});
+
+		// Give the loop every chance to run if pausing didn't actually block it.
+		sleep(Duration::from_millis(50)).await;
+		assert_eq!(processed.load(Ordering::Relaxed), 0);
+
+		pause_state.resume();
+		handle.await.expect("loop task panicked");
+		assert_eq!(processed.load(Ordering::Relaxed), 3);
+	}
+}
@@ -70,7 +70,7 @@ impl Category {
 			| Category::Databases
 			| Category::Archives
 			| Category::Applications
-			| Category::Books => object::kind::equals(Some(self.to_object_kind() as i32)),
+			| Category::Books => object::kind::equals(Some(self.to_object_kind().as_i32())),
 			_ => object::id::equals(-1),
 		}
 	}
@@ -5,7 +5,14 @@ use crate::{
 	},
 	location::file_path_helper::{file_path_to_full_path, IsolatedFilePathData},
 	notifications,
-	object::{media::thumbnail::get_thumbnail_path, orphan_remover::OrphanRemoverActor},
+	object::{
+		file_identifier::{
+			file_identifier_job::FileIdentifierReport, FileIdentifierEvent, FileIdentifierEvents,
+			FileIdentifierReportSnapshot,
+		},
+		media::thumbnail::get_thumbnail_path,
+		orphan_remover::OrphanRemoverActor,
+	},
 	prisma::{file_path, location, PrismaClient},
 	sync,
 	util::{db::maybe_missing, error::FileIOError},
@@ -58,6 +65,13 @@ pub struct Library {
 	// Look, I think this shouldn't be here but our current invalidation system needs it.
 	// TODO(@Oscar): Get rid of this with the new invalidation system.
 	event_bus_tx: broadcast::Sender<CoreEvent>,
+
+	/// KEEP PRIVATE: subscribe through `Self::subscribe_file_identifier_events`.
+	pub(crate) file_identifier_events: FileIdentifierEvents,
+
+	/// KEEP PRIVATE: read through `Self::file_identifier_report_snapshot`.
+	/// Written directly by the file identifier job's `execute_step`.
+	pub(crate) file_identifier_report_snapshot: FileIdentifierReportSnapshot,
 }
 
 impl Debug for Library {
@@ -94,9 +108,26 @@ impl Library {
 			notifications: node.notifications.clone(),
 			instance_uuid,
 			event_bus_tx: node.event_bus.0.clone(),
+			file_identifier_events: FileIdentifierEvents::default(),
+			file_identifier_report_snapshot: FileIdentifierReportSnapshot::default(),
 		})
 	}
 
+	/// Subscribes to the file identifier job's event stream. See
+	/// [`FileIdentifierEvent`] for what's emitted.
+	pub fn subscribe_file_identifier_events(&self) -> broadcast::Receiver<FileIdentifierEvent> {
+		self.file_identifier_events.subscribe()
+	}
+
+	/// Returns the running [`FileIdentifierReport`] totals as of the most
+	/// recently completed chunk of the last (or currently running) file
+	/// identifier job, or `None` if none has completed a chunk yet. Lets the
+	/// API render live created/linked/ignored counts while a job is still in
+	/// progress, rather than only once it finishes.
+	pub fn file_identifier_report_snapshot(&self) -> Option<FileIdentifierReport> {
+		self.file_identifier_report_snapshot.get()
+	}
+
 	pub fn config(&self) -> LibraryConfig {
 		// We use a `std::sync::RwLock` as we don't want users holding this over await points.
 		// We currently `.clone()` the value so that will never be a problem, however we could avoid cloning here but that makes for potentially confusing `!Send` errors.
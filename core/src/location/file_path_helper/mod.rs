@@ -37,6 +37,11 @@ file_path::select!(file_path_for_file_identifier {
 	is_dir
 	name
 	extension
+	integrity_checksum
+	sha256_checksum
+	identification_failure_count
+	object_id
+	size_in_bytes_bytes
 });
 file_path::select!(file_path_for_object_validator {
 	pub_id
@@ -55,6 +60,15 @@ file_path::select!(file_path_for_media_processor {
 	cas_id
 	object_id
 });
+file_path::select!(file_path_for_cas_verifier {
+	pub_id
+	materialized_path
+	is_dir
+	name
+	extension
+	cas_id
+	cas_id_version
+});
 file_path::select!(file_path_to_isolate {
 	location_id
 	materialized_path
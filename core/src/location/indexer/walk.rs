@@ -358,10 +358,9 @@ where
 						// Datetimes stored in DB loses a bit of precision, so we need to check against a delta
 						// instead of using != operator
 						if inode != metadata.inode
-							|| device != metadata.device || DateTime::<FixedOffset>::from(
-							metadata.modified_at,
-						) - *date_modified
-							> Duration::milliseconds(1)
+							|| device != metadata.device
+							|| DateTime::<FixedOffset>::from(metadata.modified_at) - *date_modified
+								> Duration::milliseconds(1)
 						{
 							to_update.push(
 								(sd_utils::from_bytes_to_uuid(&file_path.pub_id), entry).into(),
@@ -233,6 +233,7 @@ async fn inner_create_file(
 		cas_id,
 		kind,
 		fs_metadata,
+		..
 	} = FileMetadata::new(&location_path, &iso_file_path).await?;
 
 	debug!("Creating path: {}", iso_file_path);
@@ -261,7 +262,7 @@ async fn inner_create_file(
 					object::date_created::set(Some(
 						DateTime::<Local>::from(fs_metadata.created_or_now()).into(),
 					)),
-					object::kind::set(Some(kind as i32)),
+					object::kind::set(Some(kind.as_i32())),
 				],
 			)
 			.select(object_just_id::select())
@@ -408,6 +409,7 @@ async fn inner_update_file(
 		cas_id,
 		fs_metadata,
 		kind,
+		..
 	} = FileMetadata::new(&location_path, &iso_file_path).await?;
 
 	let (inode, device) = if let Some((inode, device)) = maybe_new_inode_and_device {
@@ -552,7 +554,7 @@ async fn inner_update_file(
 				}
 			}
 
-			let int_kind = kind as i32;
+			let int_kind = kind.as_i32();
 
 			if object.kind.map(|k| k != int_kind).unwrap_or_default() {
 				sync.write_op(
@@ -444,10 +444,7 @@ pub async fn scan_location(
 	.with_action("scan_location")
 	.with_metadata(json!({"location": location_base_data.clone()}))
 	.build()
-	.queue_next(FileIdentifierJobInit {
-		location: location_base_data.clone(),
-		sub_path: None,
-	})
+	.queue_next(FileIdentifierJobInit::new(location_base_data.clone(), None))
 	.queue_next(MediaProcessorJobInit {
 		location: location_base_data,
 		sub_path: None,
@@ -483,10 +480,10 @@ pub async fn scan_location_sub_path(
 		"sub_path": sub_path.clone(),
 	}))
 	.build()
-	.queue_next(FileIdentifierJobInit {
-		location: location_base_data.clone(),
-		sub_path: Some(sub_path.clone()),
-	})
+	.queue_next(FileIdentifierJobInit::new(
+		location_base_data.clone(),
+		Some(sub_path.clone()),
+	))
 	.queue_next(MediaProcessorJobInit {
 		location: location_base_data,
 		sub_path: Some(sub_path),
@@ -513,7 +510,7 @@ pub async fn light_scan_location(
 	let location_base_data = location::Data::from(&location);
 
 	indexer::shallow(&location, &sub_path, &node, &library).await?;
-	file_identifier::shallow(&location_base_data, &sub_path, &library).await?;
+	file_identifier::shallow(&location_base_data, &sub_path, &library, None).await?;
 	media_processor::shallow(&location_base_data, &sub_path, &library, &node).await?;
 
 	Ok(())
@@ -631,7 +628,8 @@ async fn create_location(
 		.location()
 		.count(vec![location::path::equals(Some(path.clone()))])
 		.exec()
-		.await? > 0
+		.await?
+		> 0
 	{
 		return Err(LocationError::LocationAlreadyExists(
 			location_path.as_ref().to_path_buf(),
@@ -811,6 +809,8 @@ impl From<location_with_indexer_rules::Data> for location::Data {
 			sync_preview_media: data.sync_preview_media,
 			hidden: data.hidden,
 			date_created: data.date_created,
+			hashing_throughput_mbps: None,
+			identifier_settings: None,
 			file_paths: None,
 			indexer_rules: None,
 			instance: None,
@@ -833,6 +833,8 @@ impl From<&location_with_indexer_rules::Data> for location::Data {
 			sync_preview_media: data.sync_preview_media,
 			hidden: data.hidden,
 			date_created: data.date_created,
+			hashing_throughput_mbps: None,
+			identifier_settings: None,
 			file_paths: None,
 			indexer_rules: None,
 			instance: None,
@@ -158,7 +158,7 @@ pub async fn walk(
 				.unwrap_or(ObjectKind::Unknown);
 
 			let thumbnail_key = if matches!(kind, ObjectKind::Image | ObjectKind::Video) {
-				if let Ok(cas_id) = generate_cas_id(&entry_path, metadata.len())
+				if let Ok(cas_id) = generate_cas_id(&entry_path, metadata.len(), None)
 					.await
 					.map_err(|e| errors.push(NonIndexedLocationError::from((path, e)).into()))
 				{
@@ -192,7 +192,7 @@ pub async fn walk(
 					path: entry_path,
 					name,
 					extension,
-					kind: kind as i32,
+					kind: kind.as_i32(),
 					is_dir: false,
 					date_created: metadata.created_or_now().into(),
 					date_modified: metadata.modified_or_now().into(),
@@ -238,7 +238,7 @@ pub async fn walk(
 					path: directory,
 					name,
 					extension: "".to_string(),
-					kind: ObjectKind::Folder as i32,
+					kind: ObjectKind::Folder.as_i32(),
 					is_dir: true,
 					date_created: metadata.created_or_now().into(),
 					date_modified: metadata.modified_or_now().into(),
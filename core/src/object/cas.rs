@@ -1,10 +1,19 @@
-use std::path::Path;
+use std::{
+	borrow::Cow,
+	fs::Metadata,
+	hash::{Hash, Hasher as StdHasher},
+	path::{Path, PathBuf},
+	time::{Duration, Instant},
+};
 
 use blake3::Hasher;
+use sd_file_ext::kind::ObjectKind;
+use serde::{Deserialize, Serialize};
 use static_assertions::const_assert;
 use tokio::{
-	fs::{self, File},
-	io::{self, AsyncReadExt, AsyncSeekExt, SeekFrom},
+	fs::File,
+	io::{self, AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, SeekFrom},
+	sync::Mutex,
 };
 
 const SAMPLE_COUNT: u64 = 4;
@@ -20,43 +29,1445 @@ const_assert!((HEADER_OR_FOOTER_SIZE * 2 + SAMPLE_COUNT * SAMPLE_SIZE) < MINIMUM
 // Asserting that the sample size is larger than header/footer size, as the same buffer is used for both
 const_assert!(SAMPLE_SIZE > HEADER_OR_FOOTER_SIZE);
 
-pub async fn generate_cas_id(path: impl AsRef<Path>, size: u64) -> Result<String, io::Error> {
+/// Shared `cas_id` assigned to every zero-byte file when a job opts in to
+/// linking them together, instead of calling [`generate_cas_id`] (which can't
+/// sample any content from an empty file anyway).
+pub const EMPTY_FILE_CAS_ID: &str = "0000000000000000";
+
+/// Canonical length, in characters, of every `cas_id` this module produces:
+/// 16 lowercase hex characters (64 bits) truncated from a full BLAKE3 hash,
+/// whether sampled ([`hash_sampled_reader_with_config`]), head-hashed
+/// ([`hash_head_bytes`]), or the shared [`EMPTY_FILE_CAS_ID`] sentinel. See
+/// [`is_valid_cas_id`].
+pub const CAS_ID_LEN: usize = 16;
+
+/// Bumped whenever [`hash_sampled_reader`]'s sampling scheme changes in a way
+/// that alters the resulting `cas_id` for the same content. Stored alongside
+/// every `cas_id` so a version bump can force re-identification of only the
+/// paths that were hashed under an older scheme, instead of the whole library.
+/// Used as-is for [`SamplingConfig::default`]; see [`sampled_cas_id_version`]
+/// for how a non-default config derives its own distinct version from this.
+pub const CAS_ID_VERSION: i32 = 1;
+
+/// Checks that `cas_id` is in the canonical form every `cas_id` this module
+/// generates: exactly [`CAS_ID_LEN`] lowercase hex characters. Meant for a
+/// `cas_id` read back out of the database — synced in from a peer, or
+/// surviving from an older schema — before it's trusted for an equality or
+/// `cas_id::in_vec` comparison, since a malformed value (wrong case, wrong
+/// length, non-hex characters) can never legitimately match one this module
+/// produced and would otherwise silently fail to match anything at all
+/// instead of surfacing the corruption.
+pub fn is_valid_cas_id(cas_id: &str) -> bool {
+	cas_id.len() == CAS_ID_LEN
+		&& cas_id
+			.bytes()
+			.all(|byte| byte.is_ascii_digit() || matches!(byte, b'a'..=b'f'))
+}
+
+/// Tunable knobs behind [`hash_sampled_reader`]'s sampling strategy. The
+/// `Default` impl reproduces today's fixed behavior exactly (4 samples of
+/// 10KiB each, always including an 8KiB head and tail), so existing callers
+/// that don't care about this keep getting byte-identical `cas_id`s.
+///
+/// Lowering `sample_count`/`sample_size` (or disabling `include_head_and_tail`)
+/// trades dedup precision for speed — fewer bytes read means more
+/// similarly-structured files (e.g. container formats sharing a fixed header)
+/// collide on the sampled `cas_id` despite differing content elsewhere.
+/// Raising them does the opposite.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SamplingConfig {
+	/// How many evenly-spaced chunks are sampled from the file's interior,
+	/// beyond the head/tail.
+	pub sample_count: u64,
+	/// Size, in bytes, of each interior chunk sampled.
+	pub sample_size: u64,
+	/// Whether the file's head and tail are always hashed in addition to the
+	/// interior samples, the same as today's fixed behavior. Disabling this
+	/// skips two reads per file, at the cost of missing changes confined to
+	/// the very start or end of the file.
+	pub include_head_and_tail: bool,
+	/// Once `size` is at least this many bytes, [`generate_cas_id_with_config`]
+	/// tries reading `path` through a memory map (see [`hash_sampled_mmap`])
+	/// instead of the usual chunked async reads, falling back to the async
+	/// path if the platform or filesystem can't mmap it. Scattered-offset
+	/// reads (this sampling scheme's whole point) tend to be cheaper through
+	/// a mapping than through repeated async `seek`/`read` syscalls on a large
+	/// file. `None` (the default) never mmaps. Excluded from `PartialEq`/
+	/// `Hash` below, and so from [`sampled_cas_id_version`]'s default check
+	/// and hash: the two read paths produce byte-identical output by
+	/// construction, so this is purely a performance knob, not part of the
+	/// sampling scheme that determines `cas_id`.
+	pub mmap_threshold_bytes: Option<u64>,
+	/// When `true`, [`generate_cas_id_with_config`] mixes the file's
+	/// normalized extension (lower-cased, no leading dot; empty string when
+	/// there isn't one) into the sampled hash, so two files of different
+	/// types whose sampled bytes happen to coincide (e.g. a `.txt` and a
+	/// `.bin` sharing the same padding) can never collide on `cas_id`. Off by
+	/// default, matching prior behavior: whether two files are the "same"
+	/// content has historically never depended on their extension. Unlike
+	/// `mmap_threshold_bytes`, this does change the hashed bytes, so it's
+	/// included in `PartialEq`/`Hash` below and so in [`sampled_cas_id_version`].
+	pub mix_extension_into_cas_id: bool,
+}
+
+impl PartialEq for SamplingConfig {
+	fn eq(&self, other: &Self) -> bool {
+		self.sample_count == other.sample_count
+			&& self.sample_size == other.sample_size
+			&& self.include_head_and_tail == other.include_head_and_tail
+			&& self.mix_extension_into_cas_id == other.mix_extension_into_cas_id
+	}
+}
+
+impl Eq for SamplingConfig {}
+
+impl Hash for SamplingConfig {
+	fn hash<H: StdHasher>(&self, state: &mut H) {
+		self.sample_count.hash(state);
+		self.sample_size.hash(state);
+		self.include_head_and_tail.hash(state);
+		self.mix_extension_into_cas_id.hash(state);
+	}
+}
+
+impl Default for SamplingConfig {
+	fn default() -> Self {
+		Self {
+			sample_count: SAMPLE_COUNT,
+			sample_size: SAMPLE_SIZE,
+			include_head_and_tail: true,
+			mmap_threshold_bytes: None,
+			mix_extension_into_cas_id: false,
+		}
+	}
+}
+
+/// Derives the `cas_id_version` stored alongside a `cas_id` sampled under
+/// `config`. [`SamplingConfig::default`] reproduces [`CAS_ID_VERSION`]
+/// exactly, so paths already hashed under today's fixed behavior aren't
+/// needlessly flagged for re-identification. Any other config folds its
+/// fields into a version derived from, but distinct from, `CAS_ID_VERSION` —
+/// so a path hashed under one non-default config is never mistaken for being
+/// up to date against a different config (or against the default), even if
+/// by coincidence they happened to produce the same `cas_id` for some file.
+pub fn sampled_cas_id_version(config: &SamplingConfig) -> i32 {
+	if *config == SamplingConfig::default() {
+		return CAS_ID_VERSION;
+	}
+
+	let mut hasher = std::collections::hash_map::DefaultHasher::new();
+	config.hash(&mut hasher);
+
+	CAS_ID_VERSION
+		.wrapping_add(1)
+		.wrapping_add((hasher.finish() >> 33) as i32)
+}
+
+/// The algorithm used to derive a file's content address.
+///
+/// `Sampled` is the default, battle-tested strategy used for `cas_id`. `Blake3Full`
+/// additionally hashes the entire file contents (via [`crate::object::validation::hash::file_checksum`])
+/// alongside the sampled `cas_id`, so the result can be cross-referenced with external
+/// BLAKE3-based dedup tools; it's stored separately from `cas_id` and must never be
+/// compared against a `Sampled` value as if they were the same scheme.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CasIdAlgorithm {
+	#[default]
+	Sampled,
+	Blake3Full,
+}
+
+/// Derives the `cas_id_version` stored alongside a `cas_id` produced by
+/// [`HeadHashCasIdProvider`] with the given `head_bytes`. Always distinct
+/// from [`CAS_ID_VERSION`] and from [`sampled_cas_id_version`]'s output —
+/// including for [`SamplingConfig::default`] — so
+/// [`super::file_identifier::file_identifier_job::needs_reidentification`]
+/// never mistakes a head-hash identity for a sampled one, and distinct
+/// between two different `head_bytes` values so one doesn't get mistaken
+/// for being up to date against the other.
+pub fn head_hash_cas_id_version(head_bytes: u64) -> i32 {
+	let mut hasher = std::collections::hash_map::DefaultHasher::new();
+	// Salted so this can never land on the same bucket as `sampled_cas_id_version`.
+	"head_hash".hash(&mut hasher);
+	head_bytes.hash(&mut hasher);
+
+	CAS_ID_VERSION
+		.wrapping_add(2)
+		.wrapping_add((hasher.finish() >> 33) as i32)
+}
+
+/// A token-bucket limiter bounding how many bytes per second the hashing
+/// functions below are allowed to read off disk. One bucket refills
+/// continuously at `bytes_per_sec`, capped at one second's worth of burst;
+/// [`IoRateLimiter::acquire`] is called before every read and sleeps (never
+/// spins) until enough tokens have accumulated to cover it. Meant to be
+/// `Arc`'d into [`super::file_identifier::FileMetadataOptions::io_rate_limiter`]
+/// so every concurrent hash within a chunk draws from the same bucket, the
+/// same way [`super::file_identifier::HardlinkCasIdCache`] is shared across a
+/// chunk's hardlinked files — raising `metadata_concurrency` spreads reads
+/// out over more files instead of raising the effective ceiling.
+pub struct IoRateLimiter {
+	bytes_per_sec: u64,
+	state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+	available_tokens: f64,
+	last_refill: Instant,
+}
+
+impl IoRateLimiter {
+	/// `bytes_per_sec` is the bucket's refill rate and also its capacity,
+	/// i.e. the largest burst a single `acquire` can satisfy without waiting
+	/// is one second's worth of throughput. Starts full, so the very first
+	/// read of a job isn't delayed waiting for the bucket to fill.
+	pub fn new(bytes_per_sec: u64) -> Self {
+		Self {
+			bytes_per_sec,
+			state: Mutex::new(RateLimiterState {
+				available_tokens: bytes_per_sec as f64,
+				last_refill: Instant::now(),
+			}),
+		}
+	}
+
+	/// Deducts `bytes` worth of tokens, then waits until the refill has
+	/// covered any shortfall. Returns immediately for a `bytes` of `0`, or if
+	/// enough tokens had already accumulated since the last call.
+	pub async fn acquire(&self, bytes: u64) {
+		if bytes == 0 {
+			return;
+		}
+
+		let wait = {
+			let mut state = self.state.lock().await;
+			let now = Instant::now();
+			let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+			state.available_tokens = (state.available_tokens
+				+ elapsed * self.bytes_per_sec as f64)
+				.min(self.bytes_per_sec as f64);
+			state.last_refill = now;
+
+			// Deducted up front, letting the balance go negative: a request
+			// larger than the bucket's capacity could never be covered by
+			// waiting for a refill alone, so the caller sleeps off the deficit
+			// instead, and later callers wait for it to be repaid first.
+			state.available_tokens -= bytes as f64;
+			(state.available_tokens < 0.0).then(|| {
+				Duration::from_secs_f64(-state.available_tokens / self.bytes_per_sec as f64)
+			})
+		};
+
+		if let Some(duration) = wait {
+			tokio::time::sleep(duration).await;
+		}
+	}
+}
+
+/// Callback invoked as [`generate_cas_id`] and friends read bytes off disk,
+/// with the cumulative number of bytes hashed so far (not the delta since the
+/// last call). Invoked once per already-chunked read — once per sample for
+/// [`hash_sampled_reader_with_config`], once for [`hash_head_bytes`], once
+/// (with the full byte count) for the memory-mapped path — so it's never
+/// called more often than the hashing scheme already reads from disk, and is
+/// expected to be cheap: for a huge single file this is the only feedback a
+/// caller gets between the read starting and [`generate_cas_id`] returning.
+pub type HashProgressCallback = dyn Fn(u64) + Send + Sync;
+
+/// Trait-object-friendly bound for [`FileSource::open`]'s return type: any
+/// reader that also seeks, since the sampling scheme behind
+/// [`SampledCasIdProvider`] reads scattered offsets rather than consuming a
+/// file front to back.
+pub trait AsyncReadSeek: AsyncRead + AsyncSeek + Send + Unpin {}
+impl<T: AsyncRead + AsyncSeek + Send + Unpin> AsyncReadSeek for T {}
+
+/// The subset of a file's metadata [`CasIdProvider`] and
+/// [`generate_cas_id_with_config_and_source`] actually need, decoupled from
+/// [`std::fs::Metadata`] (which nothing outside the real filesystem can
+/// construct) so a [`FileSource`] backed by something else can report its
+/// own.
+#[derive(Debug, Clone, Copy)]
+pub struct FileSourceMetadata {
+	pub len: u64,
+}
+
+impl From<&Metadata> for FileSourceMetadata {
+	fn from(metadata: &Metadata) -> Self {
+		Self { len: metadata.len() }
+	}
+}
+
+/// Abstracts how [`generate_cas_id_with_config_and_source`] (and,
+/// transitively, [`CasIdProvider`]) reads and stats a file, so a location
+/// whose real content doesn't live directly on the local filesystem at
+/// `path` — e.g. a stub pointing into a content-addressable blob store — can
+/// plug in its own resolution instead. [`LocalFileSource`] is the default
+/// for every existing caller and reproduces today's behavior exactly.
+#[async_trait::async_trait]
+pub trait FileSource: Send + Sync {
+	/// Opens `path` for reading.
+	async fn open(&self, path: &Path) -> io::Result<Box<dyn AsyncReadSeek>>;
+
+	/// Stats `path`. Only `len` is used today, to drive sampling math; kept
+	/// as its own type rather than [`std::fs::Metadata`] since nothing but
+	/// the real filesystem can construct one of those. Note that for
+	/// sampling to compute correct offsets, this must report the size of the
+	/// content [`Self::open`] actually reads, not necessarily whatever a
+	/// local stub file's own `fs::metadata` would say.
+	async fn metadata(&self, path: &Path) -> io::Result<FileSourceMetadata>;
+
+	/// Whether this source reads straight off the local filesystem, so
+	/// [`generate_cas_id_with_config_and_source`] knows it's safe to take the
+	/// mmap fast path, which bypasses [`Self::open`] entirely to map `path`
+	/// directly. `false` for anything else, since mapping a path that
+	/// doesn't hold the real content would map the wrong bytes.
+	fn is_local(&self) -> bool {
+		false
+	}
+}
+
+/// The default [`FileSource`]: reads and stats `path` straight off the local
+/// filesystem via `tokio::fs`, reproducing exactly what every caller here did
+/// before [`FileSource`] existed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalFileSource;
+
+#[async_trait::async_trait]
+impl FileSource for LocalFileSource {
+	async fn open(&self, path: &Path) -> io::Result<Box<dyn AsyncReadSeek>> {
+		Ok(Box::new(File::open(path).await?))
+	}
+
+	async fn metadata(&self, path: &Path) -> io::Result<FileSourceMetadata> {
+		tokio::fs::metadata(path)
+			.await
+			.map(|metadata| FileSourceMetadata::from(&metadata))
+	}
+
+	fn is_local(&self) -> bool {
+		true
+	}
+}
+
+/// Derives a file's content address given its path, filesystem metadata, and
+/// resolved [`ObjectKind`]. [`SampledCasIdProvider`] — the byte-sampling scheme
+/// behind [`generate_cas_id`] — is the default for every kind, but an
+/// `Arc<dyn CasIdProvider>` can be swapped into [`FileMetadataOptions`](
+/// super::file_identifier::FileMetadataOptions)`::cas_id_provider` to address
+/// specific kinds differently, e.g. perceptual hashing for images while
+/// everything else keeps sampling.
+#[async_trait::async_trait]
+pub trait CasIdProvider: Send + Sync {
+	/// `source` is how `path`'s bytes are actually read; see [`FileSource`].
+	/// Every provider here reads through it rather than opening `path`
+	/// directly, so [`FileMetadataOptions::file_source`](
+	/// super::file_identifier::FileMetadataOptions::file_source) applies
+	/// regardless of which provider a location is configured with.
+	async fn cas_id(
+		&self,
+		path: &Path,
+		metadata: &FileSourceMetadata,
+		kind: ObjectKind,
+		source: &dyn FileSource,
+		rate_limiter: Option<&IoRateLimiter>,
+		progress: Option<&HashProgressCallback>,
+	) -> Result<String, io::Error>;
+
+	/// The `cas_id_version` to store alongside a `cas_id` produced by this
+	/// provider. Providers whose output format never changes can rely on the
+	/// default; [`SampledCasIdProvider`] overrides this to account for
+	/// [`SamplingConfig`], so [`super::file_identifier::file_identifier_job::needs_reidentification`]
+	/// can tell a `cas_id` sampled under one config apart from another.
+	fn cas_id_version(&self) -> i32 {
+		CAS_ID_VERSION
+	}
+}
+
+/// The default [`CasIdProvider`]: [`generate_cas_id`]'s byte-sampling scheme,
+/// tunable via the wrapped [`SamplingConfig`] but unaffected by the file's
+/// [`ObjectKind`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SampledCasIdProvider(pub SamplingConfig);
+
+#[async_trait::async_trait]
+impl CasIdProvider for SampledCasIdProvider {
+	async fn cas_id(
+		&self,
+		path: &Path,
+		metadata: &FileSourceMetadata,
+		_kind: ObjectKind,
+		source: &dyn FileSource,
+		rate_limiter: Option<&IoRateLimiter>,
+		progress: Option<&HashProgressCallback>,
+	) -> Result<String, io::Error> {
+		generate_cas_id_with_config_and_source(path, metadata.len, &self.0, rate_limiter, progress, source)
+			.await
+	}
+
+	fn cas_id_version(&self) -> i32 {
+		sampled_cas_id_version(&self.0)
+	}
+}
+
+/// Normalizes `path`'s extension the way [`SamplingConfig::mix_extension_into_cas_id`]
+/// mixes it into the sampled hash: lower-cased, with no leading dot. A path
+/// with no extension normalizes to an empty string rather than `None`, so the
+/// mixed-in bytes are well-defined either way instead of branching on
+/// presence.
+fn normalized_extension(path: &Path) -> String {
+	path.extension()
+		.and_then(|extension| extension.to_str())
+		.unwrap_or_default()
+		.to_lowercase()
+}
+
+/// Applies Windows' extended-length path prefix (`\\?\`, or `\\?\UNC\` for a
+/// UNC path) to `path` before it's ever handed to a filesystem call, so a
+/// path deep enough to exceed `MAX_PATH` (260 characters) doesn't fail with
+/// `NotFound`/`PathTooLong` on NTFS — a real risk for locations with deep
+/// directory trees. A no-op for a short path, a relative one (the prefix only
+/// has meaning on an absolute path), or one that's already prefixed.
+#[cfg(windows)]
+pub fn extend_length_path(path: &Path) -> Cow<'_, Path> {
+	const MAX_PATH_LEN: usize = 260;
+
+	let path_str = path.to_string_lossy();
+
+	if path_str.len() < MAX_PATH_LEN || path_str.starts_with(r"\\?\") || !path.is_absolute() {
+		return Cow::Borrowed(path);
+	}
+
+	let extended = match path_str.strip_prefix(r"\\") {
+		Some(unc) => format!(r"\\?\UNC\{unc}"),
+		None => format!(r"\\?\{path_str}"),
+	};
+
+	Cow::Owned(PathBuf::from(extended))
+}
+
+/// No-op on every platform but Windows, where `MAX_PATH` is the actual
+/// constraint this exists to work around.
+#[cfg(not(windows))]
+pub fn extend_length_path(path: &Path) -> Cow<'_, Path> {
+	Cow::Borrowed(path)
+}
+
+/// A [`CasIdProvider`] that derives its `cas_id` from only the first
+/// `head_bytes` bytes of a file (see [`generate_head_hash_id`]), instead of
+/// sampling across its full length like [`SampledCasIdProvider`]. Meant for
+/// append-only files that grow over time — log files, e.g. — where linking
+/// by content should track the stable header rather than churning on every
+/// append. This is a distinct identity scheme from sampling, never meant to
+/// be compared against a sampled `cas_id` as if they were the same; its
+/// `cas_id_version` (see [`head_hash_cas_id_version`]) keeps the two apart.
+/// Selected per-extension via [`FileMetadataOptions`](
+/// super::file_identifier::FileMetadataOptions)`::head_hash_extensions` rather
+/// than swapped in wholesale as `cas_id_provider`, since a location mixing log
+/// files with everything else still wants normal sampling for the rest.
+#[derive(Debug, Clone, Copy)]
+pub struct HeadHashCasIdProvider(pub u64);
+
+#[async_trait::async_trait]
+impl CasIdProvider for HeadHashCasIdProvider {
+	async fn cas_id(
+		&self,
+		path: &Path,
+		metadata: &FileSourceMetadata,
+		_kind: ObjectKind,
+		source: &dyn FileSource,
+		rate_limiter: Option<&IoRateLimiter>,
+		progress: Option<&HashProgressCallback>,
+	) -> Result<String, io::Error> {
+		generate_head_hash_id_with_source(path, metadata.len, self.0, rate_limiter, progress, source).await
+	}
+
+	fn cas_id_version(&self) -> i32 {
+		head_hash_cas_id_version(self.0)
+	}
+}
+
+/// Thin wrapper over [`generate_cas_id_with_config`] using [`SamplingConfig::default`],
+/// for the common case of hashing a file already on disk with today's fixed
+/// sampling behavior.
+pub async fn generate_cas_id(
+	path: impl AsRef<Path>,
+	size: u64,
+	progress: Option<&HashProgressCallback>,
+) -> Result<String, io::Error> {
+	generate_cas_id_with_config(path, size, &SamplingConfig::default(), None, progress).await
+}
+
+/// Derives a directory-level content address as a Merkle-style hash over its
+/// children's `cas_id`s, for detecting duplicate directory trees (e.g. backup
+/// folders shared between two locations) the same way [`generate_cas_id`]
+/// detects duplicate files. A directory has no content of its own to sample —
+/// see `FileMetadata::new`'s assertion that it's never asked to — so this is
+/// deliberately a separate, synchronous function rather than another
+/// `CasIdProvider`.
+///
+/// `child_cas_ids` is sorted internally, so the result depends only on which
+/// content a directory contains, not on the order its entries happened to be
+/// read in. A subdirectory contributes its own `generate_dir_cas_id` result
+/// as one of its parent's `child_cas_ids`, so the hash composes recursively
+/// bottom-up; callers are responsible for only calling this once every direct
+/// child has already been identified (a child missing a `cas_id` is silently
+/// excluded rather than deferring the whole directory). Empty iterators are
+/// valid input, and always hash to the same value, matching an empty
+/// directory always producing the same dir `cas_id`.
+pub fn generate_dir_cas_id<'a>(child_cas_ids: impl IntoIterator<Item = &'a str>) -> String {
+	let mut sorted_cas_ids = child_cas_ids.into_iter().collect::<Vec<_>>();
+	sorted_cas_ids.sort_unstable();
+
+	let mut hasher = Hasher::new();
+	for cas_id in sorted_cas_ids {
+		hasher.update(cas_id.as_bytes());
+		// Separates entries so e.g. `["ab", "c"]` and `["a", "bc"]` (which
+		// would otherwise concatenate to the same bytes) hash differently.
+		hasher.update(b"\0");
+	}
+
+	hasher.finalize().to_hex()[..CAS_ID_LEN].to_string()
+}
+
+/// Derives a `cas_id` from only the first `head_bytes` bytes of `path` (or
+/// the whole file, if it's shorter than that), for [`HeadHashCasIdProvider`].
+/// Unlike [`generate_cas_id`], the file's total length is never mixed into
+/// the hash, so an append-only file that keeps growing (a log file, e.g.)
+/// keeps the exact same identity across every growth, as long as its header
+/// stays untouched — which is the entire point of this mode: sampling would
+/// otherwise assign it a new `cas_id` on every append.
+pub async fn generate_head_hash_id(
+	path: impl AsRef<Path>,
+	size: u64,
+	head_bytes: u64,
+	rate_limiter: Option<&IoRateLimiter>,
+	progress: Option<&HashProgressCallback>,
+) -> Result<String, io::Error> {
+	generate_head_hash_id_with_source(
+		path,
+		size,
+		head_bytes,
+		rate_limiter,
+		progress,
+		&LocalFileSource,
+	)
+	.await
+}
+
+/// Like [`generate_head_hash_id`], but reading `path` through `source`
+/// instead of assuming it's directly on the local filesystem. See
+/// [`FileSource`].
+pub async fn generate_head_hash_id_with_source(
+	path: impl AsRef<Path>,
+	size: u64,
+	head_bytes: u64,
+	rate_limiter: Option<&IoRateLimiter>,
+	progress: Option<&HashProgressCallback>,
+	source: &dyn FileSource,
+) -> Result<String, io::Error> {
+	let mut reader = source.open(path.as_ref()).await?;
+	hash_head_bytes(&mut reader, size, head_bytes, rate_limiter, progress).await
+}
+
+/// Core hashing logic behind [`generate_head_hash_id`], generic over any
+/// `AsyncRead` source rather than a path. When `rate_limiter` is set, waits
+/// for enough tokens to cover the read before issuing it, so a caller
+/// bounding disk I/O (see [`IoRateLimiter`]) gets throttled here the same way
+/// as in [`hash_sampled_reader_with_config`].
+pub async fn hash_head_bytes(
+	reader: &mut (impl AsyncRead + Unpin),
+	size: u64,
+	head_bytes: u64,
+	rate_limiter: Option<&IoRateLimiter>,
+	progress: Option<&HashProgressCallback>,
+) -> Result<String, io::Error> {
+	let mut hasher = Hasher::new();
+
+	let mut buf = vec![0; head_bytes.min(size) as usize];
+	if let Some(rate_limiter) = rate_limiter {
+		rate_limiter.acquire(buf.len() as u64).await;
+	}
+	reader.read_exact(&mut buf).await?;
+	hasher.update(&buf);
+
+	if let Some(progress) = progress {
+		progress(buf.len() as u64);
+	}
+
+	Ok(hasher.finalize().to_hex()[..CAS_ID_LEN].to_string())
+}
+
+/// Like [`generate_cas_id`], but sampling `path` according to `config` instead
+/// of today's fixed defaults.
+pub async fn generate_cas_id_with_config(
+	path: impl AsRef<Path>,
+	size: u64,
+	config: &SamplingConfig,
+	rate_limiter: Option<&IoRateLimiter>,
+	progress: Option<&HashProgressCallback>,
+) -> Result<String, io::Error> {
+	generate_cas_id_with_config_and_source(path, size, config, rate_limiter, progress, &LocalFileSource)
+		.await
+}
+
+/// Like [`generate_cas_id_with_config`], but reading `path` through `source`
+/// instead of assuming it's directly on the local filesystem. See
+/// [`FileSource`]. The mmap fast path is only ever attempted for
+/// [`FileSource::is_local`] sources, since it bypasses `source` entirely to
+/// map `path` straight off disk.
+pub async fn generate_cas_id_with_config_and_source(
+	path: impl AsRef<Path>,
+	size: u64,
+	config: &SamplingConfig,
+	rate_limiter: Option<&IoRateLimiter>,
+	progress: Option<&HashProgressCallback>,
+	source: &dyn FileSource,
+) -> Result<String, io::Error> {
+	let path = path.as_ref();
+	// A non-local `source` (e.g. a blob-store stub) doesn't necessarily read
+	// straight off `path` as a real filesystem path at all, so the Windows
+	// long-path prefix only makes sense to apply for a local one.
+	let path = if source.is_local() {
+		extend_length_path(path)
+	} else {
+		Cow::Borrowed(path)
+	};
+	let path = path.as_ref();
+	let extension = config
+		.mix_extension_into_cas_id
+		.then(|| normalized_extension(path));
+
+	if source.is_local()
+		&& config
+			.mmap_threshold_bytes
+			.is_some_and(|threshold| size >= threshold)
+	{
+		if let Some(cas_id) =
+			try_hash_sampled_mmap(path, size, config, rate_limiter, extension.as_deref()).await
+		{
+			// The mmap path hashes the whole mapping in one blocking call, so
+			// there's no intermediate byte count to report; the best this can
+			// do is a single call once all of it has actually been hashed.
+			if let Some(progress) = progress {
+				progress(sampled_bytes_to_hash(size, config));
+			}
+
+			return Ok(cas_id);
+		}
+	}
+
+	let mut reader = source.open(path).await?;
+	hash_sampled_reader_with_config(
+		&mut reader,
+		size,
+		config,
+		rate_limiter,
+		extension.as_deref(),
+		progress,
+	)
+	.await
+}
+
+/// Thin wrapper over [`hash_sampled_reader_with_config`] using
+/// [`SamplingConfig::default`], generic over any `AsyncRead + AsyncSeek`
+/// source rather than a path, so callers that have bytes in hand but haven't
+/// written them to disk yet (e.g. a network stream) can reuse the exact same
+/// scheme. `size` must be the reader's total length, as it is mixed into the
+/// hash and drives the sampling math below. There's no path here to derive an
+/// extension from, so `mix_extension_into_cas_id` is a no-op for this entry
+/// point regardless of what a non-default config sets it to.
+pub async fn hash_sampled_reader(
+	reader: &mut (impl AsyncRead + AsyncSeek + Unpin),
+	size: u64,
+) -> Result<String, io::Error> {
+	hash_sampled_reader_with_config(reader, size, &SamplingConfig::default(), None, None, None)
+		.await
+}
+
+/// Core sampling/hashing logic behind [`hash_sampled_reader`], generalized
+/// over a [`SamplingConfig`] instead of the fixed `SAMPLE_COUNT`/`SAMPLE_SIZE`
+/// consts. `config.sample_count` is floored at `1` to keep the sampling loop
+/// below well-defined regardless of how a caller-supplied config is built.
+/// When `rate_limiter` is set, every read below first waits for enough
+/// tokens to become available (see [`IoRateLimiter::acquire`]) instead of
+/// issuing immediately, so a large file's reads are spread out over time
+/// rather than bursting all at once. `extension` is mixed into the hash when
+/// `config.mix_extension_into_cas_id` is set; callers that pass `Some` for a
+/// config with the flag off are simply ignored, rather than treated as an error.
+/// `progress`, when set, is called once per read issued above with the
+/// cumulative bytes hashed so far; see [`HashProgressCallback`].
+pub async fn hash_sampled_reader_with_config(
+	reader: &mut (impl AsyncRead + AsyncSeek + Unpin),
+	size: u64,
+	config: &SamplingConfig,
+	rate_limiter: Option<&IoRateLimiter>,
+	extension: Option<&str>,
+	progress: Option<&HashProgressCallback>,
+) -> Result<String, io::Error> {
 	let mut hasher = Hasher::new();
 	hasher.update(&size.to_le_bytes());
+	if config.mix_extension_into_cas_id {
+		hasher.update(extension.unwrap_or_default().as_bytes());
+	}
+
+	let mut bytes_hashed = 0u64;
+	let mut report_progress = |just_read: u64| {
+		bytes_hashed += just_read;
+		if let Some(progress) = progress {
+			progress(bytes_hashed);
+		}
+	};
 
 	if size <= MINIMUM_FILE_SIZE {
 		// For small files, we hash the whole file
-		hasher.update(&fs::read(path).await?);
+		if let Some(rate_limiter) = rate_limiter {
+			rate_limiter.acquire(size).await;
+		}
+		let mut buf = Vec::with_capacity(size as usize);
+		reader.read_to_end(&mut buf).await?;
+		hasher.update(&buf);
+		report_progress(buf.len() as u64);
 	} else {
-		let mut file = File::open(path).await?;
-		let mut buf = vec![0; SAMPLE_SIZE as usize].into_boxed_slice();
+		let sample_count = config.sample_count.max(1);
+		let mut buf = vec![0; config.sample_size as usize].into_boxed_slice();
+		let header_or_footer_size = if config.include_head_and_tail {
+			HEADER_OR_FOOTER_SIZE
+		} else {
+			0
+		};
 
 		// Hashing the header
-		let mut current_pos = file
-			.read_exact(&mut buf[..HEADER_OR_FOOTER_SIZE as usize])
-			.await? as u64;
-		hasher.update(&buf[..HEADER_OR_FOOTER_SIZE as usize]);
+		let mut current_pos = if header_or_footer_size > 0 {
+			if let Some(rate_limiter) = rate_limiter {
+				rate_limiter.acquire(header_or_footer_size).await;
+			}
+			let pos = reader
+				.read_exact(&mut buf[..header_or_footer_size as usize])
+				.await? as u64;
+			hasher.update(&buf[..header_or_footer_size as usize]);
+			report_progress(header_or_footer_size);
+			pos
+		} else {
+			0
+		};
 
 		// Sample hashing the inner content of the file
-		let seek_jump = (size - HEADER_OR_FOOTER_SIZE * 2) / SAMPLE_COUNT;
+		let seek_jump = (size - header_or_footer_size * 2) / sample_count;
 		loop {
-			file.read_exact(&mut buf).await?;
+			if let Some(rate_limiter) = rate_limiter {
+				rate_limiter.acquire(buf.len() as u64).await;
+			}
+			reader.read_exact(&mut buf).await?;
 			hasher.update(&buf);
+			report_progress(buf.len() as u64);
 
-			if current_pos >= (HEADER_OR_FOOTER_SIZE + seek_jump * (SAMPLE_COUNT - 1)) {
+			if current_pos >= (header_or_footer_size + seek_jump * (sample_count - 1)) {
 				break;
 			}
 
-			current_pos = file.seek(SeekFrom::Start(current_pos + seek_jump)).await?;
+			current_pos = reader
+				.seek(SeekFrom::Start(current_pos + seek_jump))
+				.await?;
 		}
 
 		// Hashing the footer
-		file.seek(SeekFrom::End(-(HEADER_OR_FOOTER_SIZE as i64)))
-			.await?;
-		file.read_exact(&mut buf[..HEADER_OR_FOOTER_SIZE as usize])
-			.await?;
-		hasher.update(&buf[..HEADER_OR_FOOTER_SIZE as usize]);
+		if header_or_footer_size > 0 {
+			if let Some(rate_limiter) = rate_limiter {
+				rate_limiter.acquire(header_or_footer_size).await;
+			}
+			reader
+				.seek(SeekFrom::End(-(header_or_footer_size as i64)))
+				.await?;
+			reader
+				.read_exact(&mut buf[..header_or_footer_size as usize])
+				.await?;
+			hasher.update(&buf[..header_or_footer_size as usize]);
+			report_progress(header_or_footer_size);
+		}
+	}
+
+	Ok(hasher.finalize().to_hex()[..CAS_ID_LEN].to_string())
+}
+
+/// Total bytes [`hash_sampled_bytes`]/[`hash_sampled_reader_with_config`]
+/// actually hash for a file of `size` under `config`, so
+/// [`try_hash_sampled_mmap`] can charge [`IoRateLimiter`] once up front
+/// instead of once per individual read like the async path does.
+fn sampled_bytes_to_hash(size: u64, config: &SamplingConfig) -> u64 {
+	if size <= MINIMUM_FILE_SIZE {
+		size
+	} else {
+		let header_or_footer_size = if config.include_head_and_tail {
+			HEADER_OR_FOOTER_SIZE
+		} else {
+			0
+		};
+
+		header_or_footer_size * 2 + config.sample_count.max(1) * config.sample_size
+	}
+}
+
+/// Same sampling math as [`hash_sampled_reader_with_config`], but over a
+/// fully in-memory buffer instead of an `AsyncRead + AsyncSeek`, for
+/// [`try_hash_sampled_mmap`]. Must stay in lockstep with that function byte
+/// for byte — see `mmap_and_non_mmap_cas_ids_match` — since a file's `cas_id`
+/// must never depend on which read path happened to identify it.
+fn hash_sampled_bytes(
+	bytes: &[u8],
+	size: u64,
+	config: &SamplingConfig,
+	extension: Option<&str>,
+) -> String {
+	let mut hasher = Hasher::new();
+	hasher.update(&size.to_le_bytes());
+	if config.mix_extension_into_cas_id {
+		hasher.update(extension.unwrap_or_default().as_bytes());
+	}
+
+	if size <= MINIMUM_FILE_SIZE {
+		hasher.update(bytes);
+	} else {
+		let sample_count = config.sample_count.max(1);
+		let sample_size = config.sample_size as usize;
+		let header_or_footer_size = if config.include_head_and_tail {
+			HEADER_OR_FOOTER_SIZE
+		} else {
+			0
+		};
+
+		if header_or_footer_size > 0 {
+			hasher.update(&bytes[..header_or_footer_size as usize]);
+		}
+
+		let seek_jump = (size - header_or_footer_size * 2) / sample_count;
+		for sample in 0..sample_count {
+			let start = (header_or_footer_size + sample * seek_jump) as usize;
+			hasher.update(&bytes[start..start + sample_size]);
+		}
+
+		if header_or_footer_size > 0 {
+			let start = (size - header_or_footer_size) as usize;
+			hasher.update(&bytes[start..start + header_or_footer_size as usize]);
+		}
+	}
+
+	hasher.finalize().to_hex()[..CAS_ID_LEN].to_string()
+}
+
+/// Attempts [`SamplingConfig::mmap_threshold_bytes`]'s memory-mapped read
+/// path for `path`, returning `None` (rather than an [`io::Error`]) for any
+/// failure to create the mapping — an unsupported filesystem, a file that's
+/// shrunk out from under it, anything — so [`generate_cas_id_with_config`]
+/// falls back to the normal async read path instead of failing the whole
+/// identification over what's purely a throughput optimization. Runs the
+/// actual mapping and hashing on a blocking thread, since [`memmap2::Mmap`]
+/// is a synchronous, blocking-on-page-fault API that shouldn't run directly
+/// on an async executor thread.
+#[cfg(any(unix, windows))]
+async fn try_hash_sampled_mmap(
+	path: &Path,
+	size: u64,
+	config: &SamplingConfig,
+	rate_limiter: Option<&IoRateLimiter>,
+	extension: Option<&str>,
+) -> Option<String> {
+	if let Some(rate_limiter) = rate_limiter {
+		rate_limiter
+			.acquire(sampled_bytes_to_hash(size, config))
+			.await;
+	}
+
+	let path = path.to_path_buf();
+	let config = *config;
+	let extension = extension.map(ToOwned::to_owned);
+	tokio::task::spawn_blocking(move || {
+		let file = std::fs::File::open(&path).ok()?;
+		// SAFETY: the file is only mapped for the lifetime of this call; a
+		// concurrent truncation or rewrite elsewhere could yield a `cas_id`
+		// sampled from stale or torn bytes, but no worse than the same race
+		// already risks against the async read path's own sequential reads.
+		let mmap = unsafe { memmap2::Mmap::map(&file) }.ok()?;
+		Some(hash_sampled_bytes(
+			&mmap,
+			size,
+			&config,
+			extension.as_deref(),
+		))
+	})
+	.await
+	.ok()
+	.flatten()
+}
+
+/// [`memmap2`] only supports Unix and Windows; every other target simply
+/// never mmaps, falling straight back to the async read path.
+#[cfg(not(any(unix, windows)))]
+async fn try_hash_sampled_mmap(
+	_path: &Path,
+	_size: u64,
+	_config: &SamplingConfig,
+	_rate_limiter: Option<&IoRateLimiter>,
+	_extension: Option<&str>,
+) -> Option<String> {
+	None
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+	use super::*;
+	use std::{collections::HashMap, io::Cursor, path::PathBuf};
+	use tempfile::tempdir;
+	use tokio::fs;
+
+	// `generate_cas_id` (path-based) and `hash_sampled_reader` (over an
+	// in-memory `Cursor`) must agree on the same bytes, so callers hashing a
+	// stream before it touches disk get the same cas_id as if it had been
+	// written to a file first.
+	#[tokio::test]
+	async fn path_and_cursor_hashing_agree_on_the_same_bytes() {
+		let dir = tempdir().unwrap();
+		let content = vec![0xABu8; 200 * 1024];
+		let size = content.len() as u64;
+
+		let path = dir.path().join("a.bin");
+		fs::write(&path, &content).await.unwrap();
+
+		let cas_id_from_path = generate_cas_id(&path, size, None).await.unwrap();
+		let cas_id_from_cursor = hash_sampled_reader(&mut Cursor::new(&content), size)
+			.await
+			.unwrap();
+
+		assert_eq!(cas_id_from_path, cas_id_from_cursor);
+	}
+
+	// Every `cas_id` `generate_cas_id` actually produces must pass its own
+	// canonical-form validator, so the two never drift apart.
+	#[tokio::test]
+	async fn generated_cas_id_is_valid() {
+		let dir = tempdir().unwrap();
+		let content = vec![0x11u8; 200 * 1024];
+		let size = content.len() as u64;
+
+		let path = dir.path().join("a.bin");
+		fs::write(&path, &content).await.unwrap();
+
+		let cas_id = generate_cas_id(&path, size, None).await.unwrap();
+
+		assert!(is_valid_cas_id(&cas_id));
+	}
+
+	#[test]
+	fn is_valid_cas_id_accepts_canonical_lowercase_hex() {
+		assert!(is_valid_cas_id("0123456789abcdef"));
+		assert!(is_valid_cas_id(EMPTY_FILE_CAS_ID));
+	}
+
+	#[test]
+	fn is_valid_cas_id_rejects_uppercase_hex() {
+		assert!(!is_valid_cas_id("0123456789ABCDEF"));
+	}
+
+	#[test]
+	fn is_valid_cas_id_rejects_the_wrong_length() {
+		assert!(!is_valid_cas_id("0123456789abcde"));
+		assert!(!is_valid_cas_id("0123456789abcdef0"));
+		assert!(!is_valid_cas_id(""));
+	}
+
+	#[test]
+	fn is_valid_cas_id_rejects_non_hex_characters() {
+		assert!(!is_valid_cas_id("0123456789abcdeg"));
+		assert!(!is_valid_cas_id("not-a-cas-id!!!!"));
+	}
+
+	// Two directories with the same children (regardless of read order) must
+	// get equal dir cas_ids, and a directory whose contents actually differ
+	// must not collide with either.
+	#[test]
+	fn identical_directory_contents_produce_equal_dir_cas_ids() {
+		let a = generate_dir_cas_id(["aaaaaaaaaaaaaaaa", "bbbbbbbbbbbbbbbb"]);
+		let b = generate_dir_cas_id(["bbbbbbbbbbbbbbbb", "aaaaaaaaaaaaaaaa"]);
+		let different = generate_dir_cas_id(["aaaaaaaaaaaaaaaa", "cccccccccccccccc"]);
+
+		assert!(is_valid_cas_id(&a));
+		assert_eq!(a, b);
+		assert_ne!(a, different);
+	}
+
+	#[test]
+	fn dir_cas_id_is_stable_and_distinct_from_a_child_concatenation_edge_case() {
+		// Without a separator between entries, `["ab", "c"]` and `["a", "bc"]`
+		// would hash identically; with one, they must not.
+		assert_ne!(
+			generate_dir_cas_id(["ab", "c"]),
+			generate_dir_cas_id(["a", "bc"])
+		);
+
+		// An empty directory always hashes to the same value.
+		assert_eq!(
+			generate_dir_cas_id(Vec::<&str>::new()),
+			generate_dir_cas_id(Vec::<&str>::new())
+		);
+	}
+
+	// `SampledCasIdProvider` must defer to `generate_cas_id` for its result
+	// when built with the default `SamplingConfig`, so swapping the default
+	// provider into `FileMetadataOptions` is a no-op for the existing sampled
+	// behavior.
+	#[tokio::test]
+	async fn sampled_provider_agrees_with_generate_cas_id() {
+		let dir = tempdir().unwrap();
+		let content = vec![0xCDu8; 200 * 1024];
+
+		let path = dir.path().join("a.bin");
+		fs::write(&path, &content).await.unwrap();
+		let metadata = fs::metadata(&path).await.unwrap();
+
+		let from_provider = SampledCasIdProvider::default()
+			.cas_id(&path, &(&metadata).into(), ObjectKind::Unknown, &LocalFileSource, None, None)
+			.await
+			.unwrap();
+		let from_function = generate_cas_id(&path, metadata.len(), None).await.unwrap();
+
+		assert_eq!(from_provider, from_function);
+		assert_eq!(
+			SampledCasIdProvider::default().cas_id_version(),
+			CAS_ID_VERSION
+		);
+	}
+
+	// A non-default `SamplingConfig` must still reproduce the same cas_id as
+	// hashing with that config directly (i.e. `generate_cas_id_with_config`
+	// is the only sampling logic involved, with no hidden default mixed in),
+	// while getting a `cas_id_version` distinct from the default config's.
+	#[tokio::test]
+	async fn custom_sampling_config_changes_cas_id_and_version() {
+		let dir = tempdir().unwrap();
+		let content = vec![0xEFu8; 200 * 1024];
+
+		let path = dir.path().join("a.bin");
+		fs::write(&path, &content).await.unwrap();
+		let metadata = fs::metadata(&path).await.unwrap();
+
+		let custom_config = SamplingConfig {
+			sample_count: 2,
+			sample_size: 1024 * 4,
+			include_head_and_tail: false,
+			mmap_threshold_bytes: None,
+			mix_extension_into_cas_id: false,
+		};
+
+		let from_provider = SampledCasIdProvider(custom_config)
+			.cas_id(&path, &(&metadata).into(), ObjectKind::Unknown, &LocalFileSource, None, None)
+			.await
+			.unwrap();
+		let from_function =
+			generate_cas_id_with_config(&path, metadata.len(), &custom_config, None, None)
+				.await
+				.unwrap();
+
+		assert_eq!(from_provider, from_function);
+		assert_ne!(
+			from_provider,
+			generate_cas_id(&path, metadata.len(), None).await.unwrap()
+		);
+		assert_ne!(
+			SampledCasIdProvider(custom_config).cas_id_version(),
+			CAS_ID_VERSION
+		);
+	}
+
+	// Two distinct non-default configs must never collide on `cas_id_version`,
+	// so `needs_reidentification` can't mistake a path hashed under one for
+	// being up to date against the other.
+	#[test]
+	fn distinct_sampling_configs_get_distinct_versions() {
+		let a = SamplingConfig {
+			sample_count: 2,
+			sample_size: 1024 * 4,
+			include_head_and_tail: false,
+			mmap_threshold_bytes: None,
+			mix_extension_into_cas_id: false,
+		};
+		let b = SamplingConfig {
+			sample_count: 8,
+			sample_size: 1024 * 4,
+			include_head_and_tail: false,
+			mmap_threshold_bytes: None,
+			mix_extension_into_cas_id: false,
+		};
+
+		assert_ne!(sampled_cas_id_version(&a), sampled_cas_id_version(&b));
+		assert_eq!(
+			sampled_cas_id_version(&SamplingConfig::default()),
+			CAS_ID_VERSION
+		);
+	}
+
+	// A file that keeps growing by appending new content must keep the exact
+	// same head-hash identity across every growth, as long as its header is
+	// untouched — the entire point of `HeadHashCasIdProvider` over sampling.
+	#[tokio::test]
+	async fn head_hash_is_stable_across_appends_to_a_growing_file() {
+		let dir = tempdir().unwrap();
+		let path = dir.path().join("app.log");
+		let header = vec![0xAAu8; 512];
+
+		fs::write(&path, &header).await.unwrap();
+		let metadata = fs::metadata(&path).await.unwrap();
+		let initial = HeadHashCasIdProvider(256)
+			.cas_id(&path, &(&metadata).into(), ObjectKind::Unknown, &LocalFileSource, None, None)
+			.await
+			.unwrap();
+
+		let mut grown = header.clone();
+		grown.extend(vec![0xBBu8; 4096]);
+		fs::write(&path, &grown).await.unwrap();
+		let metadata = fs::metadata(&path).await.unwrap();
+		let after_growth = HeadHashCasIdProvider(256)
+			.cas_id(&path, &(&metadata).into(), ObjectKind::Unknown, &LocalFileSource, None, None)
+			.await
+			.unwrap();
+
+		assert_eq!(initial, after_growth);
+	}
+
+	// Changing the header itself, unlike appending past it, must change the
+	// head-hash identity.
+	#[tokio::test]
+	async fn head_hash_changes_when_the_header_changes() {
+		let dir = tempdir().unwrap();
+		let path = dir.path().join("app.log");
+
+		fs::write(&path, vec![0xAAu8; 512]).await.unwrap();
+		let metadata = fs::metadata(&path).await.unwrap();
+		let first = HeadHashCasIdProvider(256)
+			.cas_id(&path, &(&metadata).into(), ObjectKind::Unknown, &LocalFileSource, None, None)
+			.await
+			.unwrap();
+
+		fs::write(&path, vec![0xCCu8; 512]).await.unwrap();
+		let metadata = fs::metadata(&path).await.unwrap();
+		let second = HeadHashCasIdProvider(256)
+			.cas_id(&path, &(&metadata).into(), ObjectKind::Unknown, &LocalFileSource, None, None)
+			.await
+			.unwrap();
+
+		assert_ne!(first, second);
+	}
+
+	// `head_hash_cas_id_version` must never collide with `CAS_ID_VERSION`,
+	// `sampled_cas_id_version`, or another `head_bytes` value's own version.
+	#[test]
+	fn head_hash_version_is_distinct_from_sampled_and_itself() {
+		assert_ne!(head_hash_cas_id_version(256), CAS_ID_VERSION);
+		assert_ne!(
+			head_hash_cas_id_version(256),
+			sampled_cas_id_version(&SamplingConfig::default())
+		);
+		assert_ne!(head_hash_cas_id_version(256), head_hash_cas_id_version(512));
+	}
+
+	// A low `bytes_per_sec` ceiling must measurably slow hashing down compared
+	// to an unthrottled run of the exact same content, proving `IoRateLimiter`
+	// actually gates the reads in `hash_sampled_reader_with_config` rather
+	// than being plumbed through and never consulted.
+	#[tokio::test]
+	async fn rate_limiter_measurably_slows_hashing() {
+		let dir = tempdir().unwrap();
+		let content = vec![0x11u8; 16 * 1024];
+		let size = content.len() as u64;
+
+		let path = dir.path().join("a.bin");
+		fs::write(&path, &content).await.unwrap();
+
+		let unthrottled_start = Instant::now();
+		generate_cas_id(&path, size, None).await.unwrap();
+		let unthrottled_elapsed = unthrottled_start.elapsed();
+
+		let limiter = IoRateLimiter::new(8 * 1024);
+		let throttled_start = Instant::now();
+		generate_cas_id_with_config(
+			&path,
+			size,
+			&SamplingConfig::default(),
+			Some(&limiter),
+			None,
+		)
+		.await
+		.unwrap();
+		let throttled_elapsed = throttled_start.elapsed();
+
+		assert!(throttled_elapsed >= Duration::from_millis(900));
+		assert!(throttled_elapsed > unthrottled_elapsed * 10);
+	}
+
+	// The mmap read path (`SamplingConfig::mmap_threshold_bytes` set below the
+	// fixture's size) must produce the exact same cas_id as the default async
+	// path, for a fixture large enough to actually exercise the interior
+	// sampling loop rather than just the small-file whole-buffer branch.
+	#[tokio::test]
+	async fn mmap_and_non_mmap_cas_ids_match() {
+		let dir = tempdir().unwrap();
+		let content = vec![0x5Au8; 2 * 1024 * 1024];
+		let size = content.len() as u64;
+
+		let path = dir.path().join("large.bin");
+		fs::write(&path, &content).await.unwrap();
+
+		let non_mmap_config = SamplingConfig::default();
+		let mmap_config = SamplingConfig {
+			mmap_threshold_bytes: Some(1024),
+			..SamplingConfig::default()
+		};
+
+		let non_mmap_cas_id =
+			generate_cas_id_with_config(&path, size, &non_mmap_config, None, None)
+				.await
+				.unwrap();
+		let mmap_cas_id = generate_cas_id_with_config(&path, size, &mmap_config, None, None)
+			.await
+			.unwrap();
+
+		assert_eq!(non_mmap_cas_id, mmap_cas_id);
+	}
+
+	// A `mmap_threshold_bytes` above the file's size must never trigger the
+	// mmap path at all, so a small file still goes through the ordinary async
+	// whole-buffer branch unchanged.
+	#[tokio::test]
+	async fn mmap_threshold_above_file_size_uses_async_path() {
+		let dir = tempdir().unwrap();
+		let content = vec![0x5Bu8; 1024];
+		let size = content.len() as u64;
+
+		let path = dir.path().join("small.bin");
+		fs::write(&path, &content).await.unwrap();
+
+		let config = SamplingConfig {
+			mmap_threshold_bytes: Some(size + 1),
+			..SamplingConfig::default()
+		};
+
+		let cas_id = generate_cas_id_with_config(&path, size, &config, None, None)
+			.await
+			.unwrap();
+		let expected = generate_cas_id(&path, size, None).await.unwrap();
+
+		assert_eq!(cas_id, expected);
+	}
+
+	// `mmap_threshold_bytes` must never affect `cas_id_version`: the two read
+	// paths are required to produce byte-identical output, so it's purely a
+	// performance knob rather than part of the sampling scheme.
+	#[test]
+	fn mmap_threshold_does_not_affect_cas_id_version() {
+		let with_mmap = SamplingConfig {
+			mmap_threshold_bytes: Some(4096),
+			..SamplingConfig::default()
+		};
+
+		assert_eq!(sampled_cas_id_version(&with_mmap), CAS_ID_VERSION);
+		assert_eq!(with_mmap, SamplingConfig::default());
+	}
+
+	// Two files with identical sampled bytes but different extensions must
+	// collide on `cas_id` under the default config (extension never mattered
+	// before), but diverge once `mix_extension_into_cas_id` is enabled — and
+	// get a `cas_id_version` distinct from the default's, so paths hashed
+	// before the flag was turned on get correctly flagged for re-identification.
+	#[tokio::test]
+	async fn extension_mixing_only_changes_cas_id_when_enabled() {
+		let dir = tempdir().unwrap();
+		let content = vec![0x42u8; 200 * 1024];
+		let size = content.len() as u64;
+
+		let txt_path = dir.path().join("a.txt");
+		let bin_path = dir.path().join("a.bin");
+		fs::write(&txt_path, &content).await.unwrap();
+		fs::write(&bin_path, &content).await.unwrap();
+
+		let default_config = SamplingConfig::default();
+		let txt_default = generate_cas_id_with_config(&txt_path, size, &default_config, None, None)
+			.await
+			.unwrap();
+		let bin_default = generate_cas_id_with_config(&bin_path, size, &default_config, None, None)
+			.await
+			.unwrap();
+		assert_eq!(txt_default, bin_default);
+
+		let mixing_config = SamplingConfig {
+			mix_extension_into_cas_id: true,
+			..SamplingConfig::default()
+		};
+		let txt_mixed = generate_cas_id_with_config(&txt_path, size, &mixing_config, None, None)
+			.await
+			.unwrap();
+		let bin_mixed = generate_cas_id_with_config(&bin_path, size, &mixing_config, None, None)
+			.await
+			.unwrap();
+		assert_ne!(txt_mixed, bin_mixed);
+		assert_ne!(txt_mixed, txt_default);
+
+		assert_ne!(sampled_cas_id_version(&mixing_config), CAS_ID_VERSION);
+	}
+
+	// `generate_cas_id`'s progress callback must fire with the cumulative
+	// bytes hashed so far, strictly increasing call over call and finishing
+	// at the file's full size — not a running delta, and not skipped for any
+	// of the header/sample/footer reads a large-enough file goes through.
+	#[tokio::test]
+	async fn progress_callback_reports_monotonically_increasing_byte_counts() {
+		let dir = tempdir().unwrap();
+		let content = vec![0x7Eu8; 200 * 1024];
+		let size = content.len() as u64;
+
+		let path = dir.path().join("a.bin");
+		fs::write(&path, &content).await.unwrap();
+
+		let samples = std::sync::Mutex::new(Vec::new());
+		let cas_id = generate_cas_id(
+			&path,
+			size,
+			Some(&|bytes_hashed: u64| samples.lock().unwrap().push(bytes_hashed)),
+		)
+		.await
+		.unwrap();
+
+		let samples = samples.into_inner().unwrap();
+		assert!(!samples.is_empty());
+		assert!(samples.windows(2).all(|pair| pair[0] < pair[1]));
+		assert_eq!(
+			*samples.last().unwrap(),
+			sampled_bytes_to_hash(size, &SamplingConfig::default())
+		);
+		assert!(!cas_id.is_empty());
+	}
+
+	/// A `FileSource` that serves bytes straight out of an in-memory map
+	/// instead of the local filesystem, keyed by the same `path` a caller
+	/// would otherwise pass to `fs::File::open`. Stands in for a real
+	/// content-addressable blob store backend in tests.
+	struct InMemoryFileSource(HashMap<PathBuf, Vec<u8>>);
+
+	#[async_trait::async_trait]
+	impl FileSource for InMemoryFileSource {
+		async fn open(&self, path: &Path) -> io::Result<Box<dyn AsyncReadSeek>> {
+			self.0
+				.get(path)
+				.map(|bytes| Box::new(Cursor::new(bytes.clone())) as Box<dyn AsyncReadSeek>)
+				.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not in blob store"))
+		}
+
+		async fn metadata(&self, path: &Path) -> io::Result<FileSourceMetadata> {
+			self.0
+				.get(path)
+				.map(|bytes| FileSourceMetadata {
+					len: bytes.len() as u64,
+				})
+				.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not in blob store"))
+		}
+	}
+
+	// A `cas_id` computed through a `FileSource` that never touches the local
+	// filesystem must match one computed the normal way over the exact same
+	// bytes, so a CAS-backed location dedupes identically to a local one.
+	#[tokio::test]
+	async fn cas_id_through_a_mock_file_source_matches_local_hashing() {
+		let content = vec![0x5Cu8; 200 * 1024];
+		let size = content.len() as u64;
+		let stub_path = PathBuf::from("/blobstore/stub.bin");
+
+		let source = InMemoryFileSource(HashMap::from([(stub_path.clone(), content.clone())]));
+		assert!(!source.is_local());
+
+		let metadata = source.metadata(&stub_path).await.unwrap();
+		assert_eq!(metadata.len, size);
+
+		let from_source = generate_cas_id_with_config_and_source(
+			&stub_path,
+			metadata.len,
+			&SamplingConfig::default(),
+			None,
+			None,
+			&source,
+		)
+		.await
+		.unwrap();
+
+		let from_local = hash_sampled_reader(&mut Cursor::new(&content), size)
+			.await
+			.unwrap();
+
+		assert_eq!(from_source, from_local);
 	}
 
-	Ok(hasher.finalize().to_hex()[..16].to_string())
+	// On every non-Windows target, `extend_length_path` must always be a
+	// pure no-op: nothing here has a `MAX_PATH` limit for it to work around.
+	#[test]
+	#[cfg(not(windows))]
+	fn extend_length_path_is_a_no_op_off_windows() {
+		let path = PathBuf::from("/a/reasonably/short/path");
+		assert_eq!(extend_length_path(&path), Cow::Borrowed(path.as_path()));
+
+		let long_path = PathBuf::from("/").join("a".repeat(300));
+		assert_eq!(
+			extend_length_path(&long_path),
+			Cow::Borrowed(long_path.as_path())
+		);
+	}
+
+	// A short path never needs the prefix, an already-prefixed path is left
+	// alone, and a long absolute drive path or UNC path each get their
+	// respective `\\?\` form.
+	#[test]
+	#[cfg(windows)]
+	fn extend_length_path_prefixes_only_long_absolute_paths() {
+		let short = PathBuf::from(r"C:\short\path.txt");
+		assert_eq!(extend_length_path(&short), Cow::Borrowed(short.as_path()));
+
+		let already_prefixed = PathBuf::from(format!(r"\\?\C:\{}", "a".repeat(300)));
+		assert_eq!(
+			extend_length_path(&already_prefixed),
+			Cow::Borrowed(already_prefixed.as_path())
+		);
+
+		let long_drive_path = PathBuf::from(format!(r"C:\{}", "a".repeat(300)));
+		assert_eq!(
+			extend_length_path(&long_drive_path).to_string_lossy(),
+			format!(r"\\?\C:\{}", "a".repeat(300))
+		);
+
+		let long_unc_path = PathBuf::from(format!(r"\\server\share\{}", "a".repeat(300)));
+		assert_eq!(
+			extend_length_path(&long_unc_path).to_string_lossy(),
+			format!(r"\\?\UNC\server\share\{}", "a".repeat(300))
+		);
+	}
+
+	// `generate_cas_id` must succeed over a path deep enough to exceed
+	// `MAX_PATH`, which would otherwise fail to even stat with `NotFound`
+	// on NTFS without the extended-length prefix `extend_length_path` applies.
+	#[tokio::test]
+	#[cfg(windows)]
+	async fn generate_cas_id_succeeds_over_a_path_longer_than_max_path() {
+		let dir = tempdir().unwrap();
+
+		let mut path = dir.path().to_path_buf();
+		while path.as_os_str().len() < 300 {
+			path = path.join("a".repeat(50));
+		}
+		fs::create_dir_all(&path).await.unwrap();
+		path = path.join("f.bin");
+
+		let content = vec![0x7Eu8; 200 * 1024];
+		fs::write(&path, &content).await.unwrap();
+
+		let cas_id = generate_cas_id(&path, content.len() as u64, None)
+			.await
+			.unwrap();
+
+		assert!(is_valid_cas_id(&cas_id));
+	}
 }
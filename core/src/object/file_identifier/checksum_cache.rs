@@ -0,0 +1,261 @@
+//! An on-disk cache mapping a path's `(size, mtime)` to a previously computed
+//! `cas_id`, so a file identifier run against a library that's already been
+//! identified before doesn't pay to re-hash every file untouched since then.
+//! Unlike [`super::HardlinkCasIdCache`] (in-memory, scoped to a single job
+//! run, keyed by inode), this persists to disk across runs and is keyed by
+//! the path itself, so it helps even when nothing in the current run shares
+//! an inode with anything else in it.
+
+use std::{
+	collections::{HashMap, VecDeque},
+	path::{Path, PathBuf},
+	sync::{Mutex, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Caps how many entries a [`ChecksumCache`] holds, evicting the oldest
+/// insertion once exceeded. Bounds the file's on-disk size regardless of how
+/// many distinct paths a long-lived library accumulates; a plain FIFO rather
+/// than true LRU, since it needs no extra bookkeeping on a cache hit.
+const MAX_ENTRIES: usize = 100_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedChecksum {
+	size: u64,
+	/// Whole seconds since `UNIX_EPOCH`. Coarser than `SystemTime`, but a
+	/// legitimate content change essentially never lands within the same
+	/// second as the previous write, and it serializes as a plain integer.
+	mtime_secs: i64,
+	cas_id: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChecksumCacheContents {
+	entries: HashMap<PathBuf, CachedChecksum>,
+	/// Insertion order of `entries`, oldest first, for `MAX_ENTRIES` eviction.
+	insertion_order: VecDeque<PathBuf>,
+}
+
+/// See the module docs. One instance is loaded (via [`Self::load`]) at the
+/// start of a job run that opts in via
+/// [`file_identifier_job::FileIdentifierJobInit::enable_checksum_cache`](
+/// super::file_identifier_job::FileIdentifierJobInit::enable_checksum_cache),
+/// shared behind an `Arc` with every concurrently-processed chunk, and
+/// flushed back with [`Self::save`] in `finalize`. A job that never opts in
+/// never touches disk for this at all.
+#[derive(Debug, Default)]
+pub struct ChecksumCache {
+	contents: Mutex<ChecksumCacheContents>,
+}
+
+impl ChecksumCache {
+	/// Where `library_id`'s checksum cache lives, alongside the rest of that
+	/// library's data rather than in the node-wide config directory, since
+	/// it's meaningless once the library it was built from is gone.
+	pub fn path_for_library(data_directory: &Path, library_id: Uuid) -> PathBuf {
+		data_directory
+			.join("checksum_cache")
+			.join(format!("{library_id}.json"))
+	}
+
+	/// Loads the cache from `path`. Starts empty (rather than failing the
+	/// job) if the file doesn't exist yet or fails to parse — this cache is
+	/// purely an optimization, so losing it just means paying full price for
+	/// re-hashing, not a correctness problem.
+	pub fn load(path: &Path) -> Self {
+		let contents = std::fs::read(path)
+			.ok()
+			.and_then(|bytes| match serde_json::from_slice(&bytes) {
+				Ok(contents) => Some(contents),
+				Err(err) => {
+					warn!(?path, %err, "Failed to parse checksum cache, starting empty");
+					None
+				}
+			})
+			.unwrap_or_default();
+
+		Self {
+			contents: Mutex::new(contents),
+		}
+	}
+
+	/// Persists the cache to `path`, creating its parent directory if it
+	/// doesn't exist yet. Best-effort, same reasoning as `load`: a write
+	/// failure is logged and swallowed rather than failing the job.
+	pub fn save(&self, path: &Path) {
+		let contents = self.contents.lock().unwrap_or_else(PoisonError::into_inner);
+
+		if let Some(parent) = path.parent() {
+			if let Err(err) = std::fs::create_dir_all(parent) {
+				warn!(?parent, %err, "Failed to create checksum cache directory");
+				return;
+			}
+		}
+
+		match serde_json::to_vec(&*contents) {
+			Ok(bytes) => {
+				if let Err(err) = std::fs::write(path, bytes) {
+					warn!(?path, %err, "Failed to write checksum cache");
+				}
+			}
+			Err(err) => warn!(%err, "Failed to serialize checksum cache"),
+		}
+	}
+
+	/// The cached `cas_id` for `path`, if one exists and `size`/`mtime_secs`
+	/// still match what was cached. Either changing is treated as a miss —
+	/// the entry is left in place rather than removed, since [`Self::insert`]
+	/// overwrites it unconditionally on the next successful hash anyway.
+	pub fn get(&self, path: &Path, size: u64, mtime_secs: i64) -> Option<String> {
+		let contents = self.contents.lock().unwrap_or_else(PoisonError::into_inner);
+
+		contents.entries.get(path).and_then(|cached| {
+			(cached.size == size && cached.mtime_secs == mtime_secs).then(|| cached.cas_id.clone())
+		})
+	}
+
+	/// Records `cas_id` for `path` at this `size`/`mtime_secs`, overwriting
+	/// whatever was cached for `path` before. Evicts the oldest entry first
+	/// if this is a new path that would push the cache over `MAX_ENTRIES`.
+	pub fn insert(&self, path: PathBuf, size: u64, mtime_secs: i64, cas_id: String) {
+		let mut contents = self.contents.lock().unwrap_or_else(PoisonError::into_inner);
+
+		if !contents.entries.contains_key(&path) {
+			contents.insertion_order.push_back(path.clone());
+
+			while contents.insertion_order.len() > MAX_ENTRIES {
+				if let Some(oldest) = contents.insertion_order.pop_front() {
+					contents.entries.remove(&oldest);
+				}
+			}
+		}
+
+		contents.entries.insert(
+			path,
+			CachedChecksum {
+				size,
+				mtime_secs,
+				cas_id,
+			},
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// A fresh insertion must be served back exactly as cached as long as
+	// `size`/`mtime_secs` still match.
+	#[test]
+	fn cache_hit_for_unchanged_size_and_mtime() {
+		let cache = ChecksumCache::default();
+		let path = PathBuf::from("/library/photo.jpg");
+
+		cache.insert(path.clone(), 1024, 1_700_000_000, "abc123".to_string());
+
+		assert_eq!(cache.get(&path, 1024, 1_700_000_000), Some("abc123".to_string()));
+	}
+
+	// A changed mtime (the file was written to since the last run) must miss,
+	// even though the path and size are otherwise identical.
+	#[test]
+	fn cache_miss_when_mtime_changed() {
+		let cache = ChecksumCache::default();
+		let path = PathBuf::from("/library/photo.jpg");
+
+		cache.insert(path.clone(), 1024, 1_700_000_000, "abc123".to_string());
+
+		assert_eq!(cache.get(&path, 1024, 1_700_000_001), None);
+	}
+
+	// A changed size must also miss, independent of mtime.
+	#[test]
+	fn cache_miss_when_size_changed() {
+		let cache = ChecksumCache::default();
+		let path = PathBuf::from("/library/photo.jpg");
+
+		cache.insert(path.clone(), 1024, 1_700_000_000, "abc123".to_string());
+
+		assert_eq!(cache.get(&path, 2048, 1_700_000_000), None);
+	}
+
+	// Round-tripping through `save`/`load` must preserve every entry exactly.
+	#[test]
+	fn save_and_load_round_trips_entries() {
+		let dir = std::env::temp_dir().join(format!(
+			"sd-checksum-cache-test-{}",
+			Uuid::new_v4()
+		));
+		let path = dir.join("cache.json");
+
+		let cache = ChecksumCache::default();
+		cache.insert(
+			PathBuf::from("/library/a.txt"),
+			10,
+			1_700_000_000,
+			"cas-a".to_string(),
+		);
+		cache.save(&path);
+
+		let loaded = ChecksumCache::load(&path);
+		assert_eq!(
+			loaded.get(&PathBuf::from("/library/a.txt"), 10, 1_700_000_000),
+			Some("cas-a".to_string())
+		);
+
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	// Loading from a path that doesn't exist yet must start empty rather than
+	// failing, since the very first run against a library has no cache file.
+	#[test]
+	fn load_from_missing_file_starts_empty() {
+		let cache = ChecksumCache::load(Path::new("/nonexistent/does-not-exist.json"));
+
+		assert_eq!(cache.get(&PathBuf::from("/library/a.txt"), 10, 1_700_000_000), None);
+	}
+
+	// Inserting past `MAX_ENTRIES` must evict the oldest entry, not the
+	// most-recently-inserted one.
+	#[test]
+	fn eviction_drops_oldest_entry_first() {
+		let cache = ChecksumCache::default();
+
+		for i in 0..MAX_ENTRIES {
+			cache.insert(
+				PathBuf::from(format!("/library/{i}.txt")),
+				1,
+				1_700_000_000,
+				format!("cas-{i}"),
+			);
+		}
+
+		// Still within bounds: the very first entry survives.
+		assert!(cache
+			.get(&PathBuf::from("/library/0.txt"), 1, 1_700_000_000)
+			.is_some());
+
+		// One more insertion pushes it over the cap, evicting entry 0.
+		cache.insert(
+			PathBuf::from(format!("/library/{MAX_ENTRIES}.txt")),
+			1,
+			1_700_000_000,
+			"cas-overflow".to_string(),
+		);
+
+		assert!(cache
+			.get(&PathBuf::from("/library/0.txt"), 1, 1_700_000_000)
+			.is_none());
+		assert!(cache
+			.get(
+				&PathBuf::from(format!("/library/{MAX_ENTRIES}.txt")),
+				1,
+				1_700_000_000
+			)
+			.is_some());
+	}
+}
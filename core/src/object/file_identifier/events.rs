@@ -0,0 +1,105 @@
+use std::sync::{Mutex, PoisonError};
+
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Capacity of the lazily-allocated [`FileIdentifierEvent`] broadcast channel.
+/// Generous enough that a subscriber which briefly falls behind (e.g. while
+/// rendering a UI update) doesn't miss events from the same chunk, without
+/// holding onto an unbounded backlog.
+const FILE_IDENTIFIER_EVENTS_CAPACITY: usize = 1024;
+
+/// A single occurrence in the lifecycle of a file identifier job step, for
+/// external observers (e.g. a monitoring dashboard) that would otherwise have
+/// to scrape `tracing` logs to know what the job is doing. See
+/// [`FileIdentifierEvents`] for how to subscribe.
+#[derive(Debug, Clone)]
+pub enum FileIdentifierEvent {
+	/// A new job step has begun gathering and identifying its chunk of orphan
+	/// paths.
+	ChunkStarted,
+	/// A single file finished having its `cas_id` computed.
+	FileHashed { cas_id: String, bytes: u64 },
+	/// A brand new Object was created for a path with no existing match.
+	/// `pub_id` identifies the path, consistent with the other per-path
+	/// variants here; `object_pub_id` is the new Object itself, for a
+	/// consumer (e.g. auto-tagging) that wants every created id without
+	/// waiting for the job to finish, rather than the bounded sample on
+	/// `FileIdentifierReport::sample_created_object_pub_ids`.
+	ObjectCreated { pub_id: Uuid, object_pub_id: Uuid },
+	/// A path was connected to an already-existing Object.
+	ObjectLinked { pub_id: Uuid },
+	/// The current step's writes (or, in a dry run, its tally) are complete.
+	ChunkCommitted,
+}
+
+/// Broadcast registry for [`FileIdentifierEvent`], reachable from
+/// [`crate::library::Library::subscribe_file_identifier_events`]. The
+/// underlying channel isn't allocated until the first subscriber calls
+/// [`Self::subscribe`], so a job running with nobody listening pays no
+/// broadcast overhead beyond a mutex check per event.
+#[derive(Default)]
+pub struct FileIdentifierEvents(Mutex<Option<broadcast::Sender<FileIdentifierEvent>>>);
+
+impl FileIdentifierEvents {
+	pub fn subscribe(&self) -> broadcast::Receiver<FileIdentifierEvent> {
+		self.0
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.get_or_insert_with(|| broadcast::channel(FILE_IDENTIFIER_EVENTS_CAPACITY).0)
+			.subscribe()
+	}
+
+	/// No-ops if nobody has ever subscribed, so the common case of running
+	/// this job with no observers doesn't even construct a channel.
+	pub(super) fn emit(&self, event: FileIdentifierEvent) {
+		if let Some(tx) = self
+			.0
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.as_ref()
+		{
+			// An error here just means every receiver has been dropped; there's
+			// nobody left to tell, so there's nothing to do about it.
+			tx.send(event).ok();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn emit_before_any_subscriber_is_a_silent_no_op() {
+		let events = FileIdentifierEvents::default();
+		// Must not panic, allocate, or otherwise misbehave with zero subscribers.
+		events.emit(FileIdentifierEvent::ChunkStarted);
+	}
+
+	#[tokio::test]
+	async fn subscriber_receives_events_emitted_after_subscribing() {
+		let events = FileIdentifierEvents::default();
+		let mut rx = events.subscribe();
+
+		events.emit(FileIdentifierEvent::ChunkStarted);
+		events.emit(FileIdentifierEvent::FileHashed {
+			cas_id: "abc".to_string(),
+			bytes: 42,
+		});
+		events.emit(FileIdentifierEvent::ChunkCommitted);
+
+		assert!(matches!(
+			rx.recv().await.unwrap(),
+			FileIdentifierEvent::ChunkStarted
+		));
+		assert!(matches!(
+			rx.recv().await.unwrap(),
+			FileIdentifierEvent::FileHashed { bytes: 42, .. }
+		));
+		assert!(matches!(
+			rx.recv().await.unwrap(),
+			FileIdentifierEvent::ChunkCommitted
+		));
+	}
+}
@@ -0,0 +1,57 @@
+use std::sync::{Mutex, PoisonError};
+
+use super::file_identifier_job::FileIdentifierReport;
+
+/// Shared holder for the most recently completed chunk's [`FileIdentifierReport`]
+/// totals, reachable from
+/// [`crate::library::Library::file_identifier_report_snapshot`]. Lets the API
+/// poll live created/linked/ignored counts while a job is still running,
+/// rather than only learning them once `finalize_file_identifier` produces
+/// the final report.
+#[derive(Default)]
+pub struct FileIdentifierReportSnapshot(Mutex<Option<FileIdentifierReport>>);
+
+impl FileIdentifierReportSnapshot {
+	/// Returns a clone of the latest snapshot, or `None` if no job has
+	/// completed a chunk yet (or the library has just started up).
+	pub fn get(&self) -> Option<FileIdentifierReport> {
+		self.0.lock().unwrap_or_else(PoisonError::into_inner).clone()
+	}
+
+	/// Overwrites the snapshot with `report`, the running total as of the
+	/// chunk that just finished. Called from `execute_step` after each
+	/// chunk, not just at `finalize`, so a poller sees counts increase over
+	/// the course of a long run.
+	pub(super) fn update(&self, report: FileIdentifierReport) {
+		*self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(report);
+	}
+
+	/// Clears any snapshot left over from a previous run, so a fresh job
+	/// doesn't briefly report stale counts from before it's processed its
+	/// first chunk.
+	pub(super) fn reset(&self) {
+		*self.0.lock().unwrap_or_else(PoisonError::into_inner) = None;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn get_before_any_update_is_none() {
+		let snapshot = FileIdentifierReportSnapshot::default();
+		assert_eq!(snapshot.get(), None);
+	}
+
+	#[test]
+	fn reset_clears_a_previous_runs_snapshot() {
+		let snapshot = FileIdentifierReportSnapshot::default();
+
+		snapshot.update(FileIdentifierReport::default());
+		assert!(snapshot.get().is_some());
+
+		snapshot.reset();
+		assert_eq!(snapshot.get(), None);
+	}
+}
@@ -15,7 +15,10 @@ use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, trace, warn};
 
-use super::{process_identifier_file_paths, FileIdentifierJobError, CHUNK_SIZE};
+use super::{
+	effective_chunk_size, effective_metadata_concurrency, process_identifier_file_paths,
+	FileIdentifierJobError, FileMetadataOptions,
+};
 
 #[derive(Serialize, Deserialize)]
 pub struct ShallowFileIdentifierJobState {
@@ -27,9 +30,12 @@ pub async fn shallow(
 	location: &location::Data,
 	sub_path: &PathBuf,
 	library: &Library,
+	chunk_size: Option<usize>,
 ) -> Result<(), JobError> {
 	let Library { db, .. } = library;
 
+	let chunk_size = effective_chunk_size(chunk_size);
+
 	debug!("Identifying orphan File Paths...");
 
 	let location_id = location.id;
@@ -67,7 +73,7 @@ pub async fn shallow(
 		return Ok(());
 	}
 
-	let task_count = (orphan_count as f64 / CHUNK_SIZE as f64).ceil() as usize;
+	let task_count = (orphan_count as f64 / chunk_size as f64).ceil() as usize;
 	debug!(
 		"Found {} orphan Paths. Will execute {} tasks...",
 		orphan_count, task_count
@@ -98,18 +104,59 @@ pub async fn shallow(
 		} = &mut data;
 
 		// get chunk of orphans to process
-		let file_paths =
-			get_orphan_file_paths(&library.db, location.id, *cursor, sub_iso_file_path).await?;
+		let file_paths = get_orphan_file_paths(
+			&library.db,
+			location.id,
+			*cursor,
+			sub_iso_file_path,
+			chunk_size,
+		)
+		.await?;
 
-		let (_, _, new_cursor) = process_identifier_file_paths(
+		let (
+			_,
+			_,
+			_,
+			total_failed_paths,
+			_,
+			_,
+			_,
+			_,
+			_,
+			_,
+			_,
+			_,
+			_,
+			_,
+			errors,
+			new_cursor,
+			_,
+			_,
+			_,
+			_,
+		) = process_identifier_file_paths(
 			location,
 			&file_paths,
 			step_number,
 			*cursor,
 			library,
 			orphan_count,
+			&FileMetadataOptions::default(),
+			effective_metadata_concurrency(None),
+			None,
+			None,
+			None,
+			None,
+			false,
+			false,
+			true,
+			None,
+			false,
 		)
 		.await?;
+		if total_failed_paths > 0 {
+			warn!("Failed to identify {total_failed_paths} file paths: {errors}");
+		}
 		*cursor = new_cursor;
 	}
 
@@ -155,10 +202,11 @@ async fn get_orphan_file_paths(
 	location_id: location::id::Type,
 	file_path_id_cursor: file_path::id::Type,
 	sub_iso_file_path: &IsolatedFilePathData<'_>,
+	chunk_size: usize,
 ) -> Result<Vec<file_path_for_file_identifier::Data>, prisma_client_rust::QueryError> {
 	trace!(
 		"Querying {} orphan Paths at cursor: {:?}",
-		CHUNK_SIZE,
+		chunk_size,
 		file_path_id_cursor
 	);
 	db.file_path()
@@ -169,7 +217,7 @@ async fn get_orphan_file_paths(
 		))
 		.order_by(file_path::id::order(SortOrder::Asc))
 		// .cursor(cursor.into())
-		.take(CHUNK_SIZE as i64)
+		.take(chunk_size as i64)
 		// .skip(1)
 		.select(file_path_for_file_identifier::select())
 		.exec()
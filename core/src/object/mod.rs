@@ -18,8 +18,10 @@ pub mod validation;
 
 // Object selectables!
 object::select!(object_for_file_identifier {
+	id
 	pub_id
-	file_paths: select { pub_id cas_id extension is_dir materialized_path name }
+	kind
+	file_paths: select { pub_id cas_id identity_key extension is_dir materialized_path name location_id }
 });
 
 // The response to provide the Explorer when looking at Objects
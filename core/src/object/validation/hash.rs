@@ -1,4 +1,5 @@
 use blake3::Hasher;
+use sha2::{Digest, Sha256};
 use std::path::Path;
 use tokio::{
 	fs::File,
@@ -22,3 +23,46 @@ pub async fn file_checksum(path: impl AsRef<Path>) -> Result<String, io::Error>
 
 	Ok(hex.to_string())
 }
+
+/// Full-content SHA-256 of `path`, for compliance/export use cases that
+/// specifically require that algorithm rather than [`file_checksum`]'s
+/// BLAKE3. Never used for dedup; see `FilePath::sha256_checksum`.
+pub async fn sha256_checksum(path: impl AsRef<Path>) -> Result<String, io::Error> {
+	let mut reader = File::open(path).await?;
+	let mut context = Sha256::new();
+	let mut buffer = vec![0; BLOCK_LEN].into_boxed_slice();
+	loop {
+		let read_count = reader.read(&mut buffer).await?;
+		context.update(&buffer[..read_count]);
+		if read_count != BLOCK_LEN {
+			break;
+		}
+	}
+
+	Ok(hex::encode(context.finalize()))
+}
+
+/// Both [`file_checksum`] and [`sha256_checksum`] over a single read of
+/// `path`, for a caller that wants both full-content hashes without paying
+/// for the file to be opened and streamed through twice.
+pub async fn blake3_and_sha256_checksums(
+	path: impl AsRef<Path>,
+) -> Result<(String, String), io::Error> {
+	let mut reader = File::open(path).await?;
+	let mut blake3_context = Hasher::new();
+	let mut sha256_context = Sha256::new();
+	let mut buffer = vec![0; BLOCK_LEN].into_boxed_slice();
+	loop {
+		let read_count = reader.read(&mut buffer).await?;
+		blake3_context.update(&buffer[..read_count]);
+		sha256_context.update(&buffer[..read_count]);
+		if read_count != BLOCK_LEN {
+			break;
+		}
+	}
+
+	Ok((
+		blake3_context.finalize().to_hex().to_string(),
+		hex::encode(sha256_context.finalize()),
+	))
+}
@@ -4,6 +4,7 @@ use std::path::Path;
 
 use thiserror::Error;
 
+pub mod cas_verifier_job;
 pub mod hash;
 pub mod validator_job;
 
@@ -3,7 +3,11 @@
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use specta::Type;
-use std::{fmt::Display, path::PathBuf, sync::OnceLock};
+use std::{
+	fmt::Display,
+	path::{Path, PathBuf},
+	sync::OnceLock,
+};
 use sysinfo::{DiskExt, System, SystemExt};
 use thiserror::Error;
 use tokio::sync::Mutex;
@@ -14,6 +18,23 @@ fn sys_guard() -> &'static Mutex<System> {
 	SYS.get_or_init(|| Mutex::new(System::new_all()))
 }
 
+/// Returns the available space, in bytes, on whichever mounted volume `path`
+/// lives on. Matches `path` against every known disk's mount point and picks
+/// the longest (most specific) one, the same longest-prefix approach the
+/// filesystem itself uses to resolve nested mounts. `None` if `path` doesn't
+/// fall under any known mount point (nothing enumerated it yet, or the path
+/// doesn't exist), leaving the caller to decide how to treat "unknown".
+pub async fn available_space_for_path(path: &Path) -> Option<u64> {
+	let mut sys = sys_guard().lock().await;
+	sys.refresh_disks_list();
+
+	sys.disks()
+		.iter()
+		.filter(|disk| path.starts_with(disk.mount_point()))
+		.max_by_key(|disk| disk.mount_point().as_os_str().len())
+		.map(|disk| disk.available_space())
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Type)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum DiskType {
@@ -1,55 +1,222 @@
 use serde::{Deserialize, Serialize};
 
-// Note: The order of this enum should never change, and always be kept in sync with `packages/client/src/utils/objectKind.ts`
-#[repr(i32)]
+/// Reserved id space for [`ObjectKind::Custom`]'s `object.kind` column value:
+/// every built-in variant's value stays below this, so a stored `kind` can
+/// be told apart as custom vs. built-in with a single comparison, and future
+/// built-in variants can be added without ever colliding with a caller's
+/// custom id.
+const CUSTOM_KIND_BASE: i32 = 1000;
+
+// Note: the order of, and values returned by `as_i32()` for, the built-in
+// variants below should never change, and must be kept in sync with
+// `packages/client/src/utils/objectKind.ts`. `Custom` is exempt: its id is
+// assigned by whoever registers the kind (see `CustomKindDefinition` in
+// `sd_core::object::file_identifier`), not a fixed discriminant.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
 pub enum ObjectKind {
 	/// A file that can not be identified by the indexer
-	Unknown = 0,
+	Unknown,
 	/// A known filetype, but without specific support
-	Document = 1,
+	Document,
 	/// A virtual filesystem directory
-	Folder = 2,
+	Folder,
 	/// A file that contains human-readable text
-	Text = 3,
+	Text,
 	/// A virtual directory int
-	Package = 4,
+	Package,
 	/// An image file
-	Image = 5,
+	Image,
 	/// An audio file
-	Audio = 6,
+	Audio,
 	/// A video file
-	Video = 7,
+	Video,
 	/// A compressed archive of data
-	Archive = 8,
+	Archive,
 	/// An executable, program or application
-	Executable = 9,
+	Executable,
 	/// A link to another object
-	Alias = 10,
+	Alias,
 	/// Raw bytes encrypted by Spacedrive with self contained metadata
-	Encrypted = 11,
+	Encrypted,
 	/// A key or certificate file
-	Key = 12,
+	Key,
 	/// A link can open web pages, apps or Spaces
-	Link = 13,
+	Link,
 	/// A special filetype that represents a preserved webpage
-	WebPageArchive = 14,
+	WebPageArchive,
 	/// A widget is a mini app that can be placed in a Space at various sizes, associated Widget struct required
-	Widget = 15,
+	Widget,
 	/// Albums can only have one level of children, and are associated with the Album struct
-	Album = 16,
+	Album,
 	/// Its like a folder, but appears like a stack of files, designed for burst photos / associated groups of files
-	Collection = 17,
+	Collection,
 	/// You know, text init
-	Font = 18,
+	Font,
 	/// 3D Object
-	Mesh = 19,
+	Mesh,
 	/// Editable source code file
-	Code = 20,
+	Code,
 	/// Database file
-	Database = 21,
+	Database,
 	/// E-book file
-	Book = 22,
+	Book,
 	/// Config file
-	Config = 23,
+	Config,
+	/// An application-specific category beyond the built-in kinds above, e.g.
+	/// "GameSave" or "DAWProject". The `u16` is whatever id the definition
+	/// that produced it was registered under; resolve it back to a display
+	/// name via `CustomKindDefinition::resolve_name` in
+	/// `sd_core::object::file_identifier`, which is also where these are
+	/// defined and consulted during identification.
+	Custom(u16),
+}
+
+impl ObjectKind {
+	/// The value stored in the `object.kind` column. Built-in variants keep
+	/// their historical values (0-23, matching this enum's previous
+	/// `#[repr(i32)]` discriminants, from back when every variant was
+	/// fieldless); `Custom(id)` is offset by [`CUSTOM_KIND_BASE`] so it can
+	/// never collide with a built-in value, including ones added later.
+	pub fn as_i32(self) -> i32 {
+		match self {
+			Self::Unknown => 0,
+			Self::Document => 1,
+			Self::Folder => 2,
+			Self::Text => 3,
+			Self::Package => 4,
+			Self::Image => 5,
+			Self::Audio => 6,
+			Self::Video => 7,
+			Self::Archive => 8,
+			Self::Executable => 9,
+			Self::Alias => 10,
+			Self::Encrypted => 11,
+			Self::Key => 12,
+			Self::Link => 13,
+			Self::WebPageArchive => 14,
+			Self::Widget => 15,
+			Self::Album => 16,
+			Self::Collection => 17,
+			Self::Font => 18,
+			Self::Mesh => 19,
+			Self::Code => 20,
+			Self::Database => 21,
+			Self::Book => 22,
+			Self::Config => 23,
+			Self::Custom(id) => CUSTOM_KIND_BASE + i32::from(id),
+		}
+	}
+
+	/// The inverse of [`Self::as_i32`], reconstructing an `ObjectKind` from
+	/// an `object.kind` column value. `None` for a value outside both the
+	/// built-in and custom ranges, which shouldn't happen for a column this
+	/// crate wrote itself; callers reading one back degrade to `Unknown`
+	/// instead of panicking.
+	pub fn from_i32(value: i32) -> Option<Self> {
+		Some(match value {
+			0 => Self::Unknown,
+			1 => Self::Document,
+			2 => Self::Folder,
+			3 => Self::Text,
+			4 => Self::Package,
+			5 => Self::Image,
+			6 => Self::Audio,
+			7 => Self::Video,
+			8 => Self::Archive,
+			9 => Self::Executable,
+			10 => Self::Alias,
+			11 => Self::Encrypted,
+			12 => Self::Key,
+			13 => Self::Link,
+			14 => Self::WebPageArchive,
+			15 => Self::Widget,
+			16 => Self::Album,
+			17 => Self::Collection,
+			18 => Self::Font,
+			19 => Self::Mesh,
+			20 => Self::Code,
+			21 => Self::Database,
+			22 => Self::Book,
+			23 => Self::Config,
+			custom
+				if (CUSTOM_KIND_BASE..=CUSTOM_KIND_BASE + i32::from(u16::MAX))
+					.contains(&custom) =>
+			{
+				Self::Custom((custom - CUSTOM_KIND_BASE) as u16)
+			}
+			_ => return None,
+		})
+	}
+}
+
+/// Returned by [`ObjectKind::try_from`] for an `i32` outside both the
+/// built-in and `Custom` ranges, i.e. one [`ObjectKind::from_i32`] would also
+/// reject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("{0} is not a valid ObjectKind discriminant")]
+pub struct InvalidObjectKindError(pub i32);
+
+impl TryFrom<i32> for ObjectKind {
+	type Error = InvalidObjectKindError;
+
+	/// Fallible counterpart to [`Self::as_i32`], for call sites that need to
+	/// know when a discriminant didn't round-trip (e.g. to assert a
+	/// just-computed `as_i32()` is representable) rather than silently
+	/// falling back to `Unknown` like [`Self::from_i32`] does. A caller that
+	/// wants the `Unknown` fallback instead should use
+	/// `ObjectKind::try_from(value).unwrap_or(ObjectKind::Unknown)`.
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		Self::from_i32(value).ok_or(InvalidObjectKindError(value))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// `from_i32` must invert `as_i32` for both a built-in variant and a
+	// `Custom` one, and must reject a value in neither range rather than
+	// silently mapping it to something plausible.
+	#[test]
+	fn from_i32_inverts_as_i32() {
+		assert_eq!(
+			ObjectKind::from_i32(ObjectKind::Image.as_i32()),
+			Some(ObjectKind::Image)
+		);
+		assert_eq!(
+			ObjectKind::from_i32(ObjectKind::Custom(42).as_i32()),
+			Some(ObjectKind::Custom(42))
+		);
+		assert_eq!(ObjectKind::from_i32(CUSTOM_KIND_BASE - 1), None);
+	}
+
+	// A valid discriminant must round-trip through `TryFrom<i32>` exactly
+	// like it does through `from_i32`.
+	#[test]
+	fn try_from_accepts_a_valid_discriminant() {
+		assert_eq!(
+			ObjectKind::try_from(ObjectKind::Video.as_i32()),
+			Ok(ObjectKind::Video)
+		);
+	}
+
+	// A value outside both the built-in and `Custom` ranges must be rejected
+	// with `InvalidObjectKindError` rather than silently mapping to something
+	// plausible.
+	#[test]
+	fn try_from_rejects_an_out_of_range_discriminant() {
+		assert_eq!(
+			ObjectKind::try_from(CUSTOM_KIND_BASE - 1),
+			Err(InvalidObjectKindError(CUSTOM_KIND_BASE - 1))
+		);
+	}
+
+	// The documented fallback for a caller that wants `Unknown` instead of a
+	// hard error must actually produce `Unknown`, not propagate the error or
+	// panic.
+	#[test]
+	fn try_from_out_of_range_falls_back_to_unknown_via_unwrap_or() {
+		let kind = ObjectKind::try_from(CUSTOM_KIND_BASE - 1).unwrap_or(ObjectKind::Unknown);
+		assert_eq!(kind, ObjectKind::Unknown);
+	}
 }
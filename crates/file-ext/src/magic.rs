@@ -1,6 +1,9 @@
 #![allow(dead_code)]
 
-use crate::extensions::{CodeExtension, Extension, VideoExtension};
+use crate::{
+	extensions::{CodeExtension, Extension, VideoExtension},
+	kind::ObjectKind,
+};
 use std::{ffi::OsStr, io::SeekFrom, path::Path};
 
 use tokio::{
@@ -234,3 +237,60 @@ impl Extension {
 		}
 	}
 }
+
+/// Number of leading bytes [`sniff_object_kind`] needs to recognize any of its
+/// signatures. Callers should read at least this many bytes (or the whole
+/// file, if shorter) before calling it.
+pub const SNIFF_HEADER_SIZE: usize = 16;
+
+/// Best-effort identification of a file's [`ObjectKind`] purely from its
+/// leading bytes, for files with no extension (or one [`Extension::
+/// resolve_conflicting`] couldn't resolve) to fall back on. Only covers a
+/// handful of unambiguous, widely-used signatures; anything else returns
+/// `None` rather than guessing.
+pub fn sniff_object_kind(buf: &[u8]) -> Option<ObjectKind> {
+	match buf {
+		[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(ObjectKind::Image),
+		[b'%', b'P', b'D', b'F', b'-', ..] => Some(ObjectKind::Document),
+		[b'P', b'K', 0x03, 0x04, ..] | [b'P', b'K', 0x05, 0x06, ..] => Some(ObjectKind::Archive),
+		[0x7F, b'E', b'L', b'F', ..] => Some(ObjectKind::Executable),
+		[_, _, _, _, b'f', b't', b'y', b'p', ..] => Some(ObjectKind::Video),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod sniff_tests {
+	use super::*;
+
+	#[test]
+	fn recognizes_known_signatures() {
+		assert_eq!(
+			sniff_object_kind(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0]),
+			Some(ObjectKind::Image)
+		);
+		assert_eq!(
+			sniff_object_kind(b"%PDF-1.7 rest of file"),
+			Some(ObjectKind::Document)
+		);
+		assert_eq!(
+			sniff_object_kind(&[b'P', b'K', 0x03, 0x04, 0, 0, 0, 0]),
+			Some(ObjectKind::Archive)
+		);
+		assert_eq!(
+			sniff_object_kind(&[0x7F, b'E', b'L', b'F', 2, 1, 1, 0]),
+			Some(ObjectKind::Executable)
+		);
+		assert_eq!(
+			sniff_object_kind(&[0, 0, 0, 0x18, b'f', b't', b'y', b'p', b'i', b's', b'o', b'm']),
+			Some(ObjectKind::Video)
+		);
+	}
+
+	#[test]
+	fn unrecognized_or_truncated_bytes_return_none() {
+		assert_eq!(sniff_object_kind(b"plain text file"), None);
+		assert_eq!(sniff_object_kind(&[0x89, b'P']), None);
+		assert_eq!(sniff_object_kind(&[]), None);
+	}
+}